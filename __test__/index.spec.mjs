@@ -1,7 +1,1178 @@
 import test from 'ava'
+import crypto from 'node:crypto'
+import fs from 'node:fs'
+import os from 'node:os'
+import path from 'node:path'
 
-import { sum } from '../index.js'
+import { sum, startMockNacos, NacosConfigClient, NacosNamingClient } from '../index.js'
+
+// Tests below run against the mock of nacos server, which is in the build of `yarn build:mock`,
+// or a real one by e.g. `NACOS_SERVER_ADDR=127.0.0.1:8848 yarn test`
+const mock = process.env.NACOS_SERVER_ADDR || !startMockNacos ? null : await startMockNacos()
+const serverAddr = process.env.NACOS_SERVER_ADDR ?? mock?.serverAddr
+const nacosTest = serverAddr ? test.serial : test.skip
+const mockTest = startMockNacos ? test : test.skip
+
+test.after.always(() => mock?.close())
+
+const sleep = (ms) => new Promise((resolve) => setTimeout(resolve, ms))
+
+let names = 0
+// unique in the server shared by the tests, even if created in the same millisecond
+const uniqueName = (prefix) => `${prefix}-${Date.now()}-${names++}`
+
+const configClient = (options, filter) => new NacosConfigClient({ serverAddr, namespace: '', ...options }, filter)
+const namingClient = (options) => new NacosNamingClient({ serverAddr, namespace: '', ...options })
+
+// A listener recording what it is called with, mapped by `map`;
+// `await listener.received(count)` waits until `count` of them are recorded, and returns them all
+const recorder = (map = (err, value) => value) => {
+  const waiters = new Set()
+  const listener = (...args) => {
+    listener.values.push(map(...args))
+    waiters.forEach((check) => check())
+  }
+  listener.values = []
+  listener.received = (count, timeoutMs = 10000) =>
+    new Promise((resolve, reject) => {
+      const timer = setTimeout(() => {
+        waiters.delete(check)
+        reject(new Error(`${count} expected, but received ${JSON.stringify(listener.values)}`))
+      }, timeoutMs)
+      const check = () => {
+        if (listener.values.length >= count) {
+          clearTimeout(timer)
+          waiters.delete(check)
+          resolve(listener.values)
+        }
+      }
+      waiters.add(check)
+      check()
+    })
+  return listener
+}
+
+// Polls `get` until `done` with what it returns, or the timeout, and returns the last one got
+const eventually = async (get, done, timeoutMs = 5000) => {
+  const deadline = Date.now() + timeoutMs
+  let value = await get()
+  while (!done(value) && Date.now() < deadline) {
+    await sleep(100)
+    value = await get()
+  }
+  return value
+}
+
+// nacos-sdk sends the listen request of a new config listener in background, and a change published before
+// the server has it is only checked again a minute later, so wait until the server lists `count` listeners of it
+const listenSettled = (dataId, count = 1) =>
+  eventually(
+    async () => {
+      const query = new URLSearchParams({ dataId, group: 'TEST_GROUP', tenant: '' })
+      const resp = await fetch(`http://${serverAddr}/nacos/v1/cs/configs/listener?${query}`)
+      return Object.keys((await resp.json()).lisentersGroupkeyStatus ?? {}).length
+    },
+    (listeners) => listeners >= count,
+  )
 
 test('sum from native', (t) => {
   t.is(sum(1, 2), 3)
 })
+
+mockTest('start and close mock nacos', async (t) => {
+  const other = await startMockNacos()
+  t.regex(other.serverAddr, /^127\.0\.0\.1:\d+$/)
+  t.not(other.serverAddr, serverAddr)
+
+  const client = configClient({ serverAddr: other.serverAddr })
+  t.true(await client.publishConfig('mock-config', 'TEST_GROUP', 'v1'))
+  t.is(await client.getConfig('mock-config', 'TEST_GROUP'), 'v1')
+  other.close()
+  other.close()
+})
+
+nacosTest('removed config listener stops receiving notify', async (t) => {
+  const client = configClient()
+  const dataId = uniqueName('remove-listener')
+  await client.publishConfig(dataId, 'TEST_GROUP', 'v1')
+
+  const kept = recorder((err, resp) => resp.content)
+  const removed = recorder((err, resp) => resp.content)
+  await client.addListener(dataId, 'TEST_GROUP', kept)
+  await client.addListener(dataId, 'TEST_GROUP', removed)
+  await listenSettled(dataId)
+
+  await client.publishConfig(dataId, 'TEST_GROUP', 'v2')
+  t.deepEqual(await kept.received(1), ['v2'])
+  t.deepEqual(await removed.received(1), ['v2'])
+
+  await client.removeListener(dataId, 'TEST_GROUP', removed)
+  await client.publishConfig(dataId, 'TEST_GROUP', 'v3')
+  t.deepEqual(await kept.received(2), ['v2', 'v3'])
+  t.deepEqual(removed.values, ['v2'])
+
+  await client.removeListener(dataId, 'TEST_GROUP', kept)
+})
+
+nacosTest('unsubscribed naming listener stops receiving instances', async (t) => {
+  const client = namingClient()
+  const serviceName = uniqueName('un-subscribe')
+
+  const kept = recorder((err, instances) => instances.length)
+  const removed = recorder((err, instances) => instances.length)
+  await client.subscribe(serviceName, 'TEST_GROUP', null, kept)
+  await client.subscribe(serviceName, 'TEST_GROUP', null, removed)
+
+  await client.registerInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port: 8080 })
+  t.deepEqual(await kept.received(1), [1])
+  t.deepEqual(await removed.received(1), [1])
+
+  await client.unSubscribe(serviceName, 'TEST_GROUP', null, removed)
+  await client.registerInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port: 8081 })
+  t.deepEqual(await kept.received(2), [1, 2])
+  t.deepEqual(removed.values, [1])
+
+  await client.unSubscribe(serviceName, 'TEST_GROUP', null, kept)
+})
+
+nacosTest('unsubscribe naming listener with clusters in another order', async (t) => {
+  const client = namingClient()
+  const serviceName = uniqueName('un-subscribe-clusters')
+
+  const kept = recorder((err, instances) => instances.length)
+  const removed = recorder((err, instances) => instances.length)
+  // the removed one is dispatched first, so it would have been called when the kept one is
+  await client.subscribe(serviceName, 'TEST_GROUP', ['a', 'b'], removed)
+  await client.subscribe(serviceName, 'TEST_GROUP', ['a', 'b'], kept)
+
+  await client.registerInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port: 8080, clusterName: 'a' })
+  t.deepEqual(await kept.received(1), [1])
+  t.deepEqual(await removed.received(1), [1])
+
+  await client.unSubscribe(serviceName, 'TEST_GROUP', ['b', 'a', 'b'], removed)
+  await client.registerInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port: 8081, clusterName: 'b' })
+  t.deepEqual(await kept.received(2), [1, 2])
+  t.deepEqual(removed.values, [1])
+
+  await client.unSubscribe(serviceName, 'TEST_GROUP', ['b', 'a'], kept)
+})
+
+nacosTest('disposed config subscription stops receiving notify', async (t) => {
+  const client = configClient()
+  const dataId = uniqueName('dispose-listener')
+  await client.publishConfig(dataId, 'TEST_GROUP', 'v1')
+
+  const received = recorder((err, resp) => resp.content)
+  const sub = await client.addListener(dataId, 'TEST_GROUP', received)
+  await listenSettled(dataId)
+  t.true(sub.active)
+
+  await client.publishConfig(dataId, 'TEST_GROUP', 'v2')
+  t.deepEqual(await received.received(1), ['v2'])
+
+  sub.dispose()
+  t.false(sub.active)
+  sub.dispose()
+  // another listener tells when v3 is notified
+  const witness = recorder((err, resp) => resp.content)
+  const witnessSub = await client.addListener(dataId, 'TEST_GROUP', witness)
+  await listenSettled(dataId)
+  await client.publishConfig(dataId, 'TEST_GROUP', 'v3')
+  t.deepEqual(await witness.received(1), ['v3'])
+  t.deepEqual(received.values, ['v2'])
+
+  witnessSub.dispose()
+})
+
+nacosTest('disposed naming subscription stops receiving instances', async (t) => {
+  const client = namingClient()
+  const serviceName = uniqueName('dispose-subscribe')
+
+  const kept = recorder((err, instances) => instances.length)
+  const removed = recorder((err, instances) => instances.length)
+  const keptSub = await client.subscribe(serviceName, 'TEST_GROUP', null, kept)
+  const removedSub = await client.subscribe(serviceName, 'TEST_GROUP', null, removed)
+
+  await client.registerInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port: 8080 })
+  t.deepEqual(await kept.received(1), [1])
+  t.deepEqual(await removed.received(1), [1])
+
+  if (typeof Symbol.dispose === 'symbol') {
+    removedSub[Symbol.dispose]()
+  } else {
+    removedSub.dispose()
+  }
+  t.false(removedSub.active)
+  t.true(keptSub.active)
+  await client.registerInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port: 8081 })
+  t.deepEqual(await kept.received(2), [1, 2])
+  t.deepEqual(removed.values, [1])
+
+  keptSub.dispose()
+})
+
+nacosTest('watch config changes with for await', async (t) => {
+  const client = configClient()
+  const dataId = uniqueName('watch')
+  await client.publishConfig(dataId, 'TEST_GROUP', 'v1')
+
+  const watcher = client.watch(dataId, 'TEST_GROUP', { overflow: 'coalesce-latest' })
+  // nacos-sdk drops one of the listeners of the same config added at once, so add the next one after it listens
+  await listenSettled(dataId)
+  // notified together with the watcher, so both changes are in it once this has them
+  const notified = recorder((err, resp) => resp.content)
+  const sub = await client.addListener(dataId, 'TEST_GROUP', notified)
+  await listenSettled(dataId)
+
+  await client.publishConfig(dataId, 'TEST_GROUP', 'v2')
+  await notified.received(1)
+  await client.publishConfig(dataId, 'TEST_GROUP', 'v3')
+  await notified.received(2)
+
+  const received = []
+  for await (const resp of watcher) {
+    received.push(resp.content)
+    if (resp.content === 'v3') {
+      break
+    }
+  }
+  // v2 is coalesced by v3, since only the newest change is kept
+  t.deepEqual(received, ['v3'])
+  t.deepEqual(await watcher.next(), { done: true })
+  sub.dispose()
+})
+
+nacosTest('watch instances coalesces to the newest snapshot', async (t) => {
+  const client = namingClient()
+  const serviceName = uniqueName('watch-instances')
+
+  const watcher = client.watchInstances(serviceName, 'TEST_GROUP')
+  // notified together with the watcher, so both changes are in it once this has them
+  const notified = recorder((err, instances) => instances.length)
+  const sub = await client.subscribe(serviceName, 'TEST_GROUP', null, notified)
+
+  await client.registerInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port: 8080 })
+  await notified.received(1)
+  await client.registerInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port: 8081 })
+  await notified.received(2)
+
+  const received = []
+  for await (const instances of watcher) {
+    received.push(instances.map((instance) => instance.port).sort())
+    break
+  }
+  t.deepEqual(received, [[8080, 8081]])
+  t.deepEqual(await watcher.next(), { done: true })
+  sub.dispose()
+})
+
+nacosTest('watcher closed while subscribing is unsubscribed in order', async (t) => {
+  const client = namingClient()
+  const serviceName = uniqueName('watch-closed')
+
+  const watcher = client.watchInstances(serviceName, 'TEST_GROUP')
+  t.deepEqual(await watcher.return(), { done: true })
+
+  // unsubscribed after its subscribe and before the next one, so the next one keeps receiving once for each change
+  const received = recorder((err, instances) => instances.length)
+  const sub = await client.subscribe(serviceName, 'TEST_GROUP', null, received)
+  await client.registerInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port: 8080 })
+  await received.received(1)
+  await client.registerInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port: 8081 })
+  t.deepEqual(await received.received(2), [1, 2])
+
+  sub.dispose()
+})
+
+nacosTest('subscribe diff of instances', async (t) => {
+  const client = namingClient()
+  const serviceName = uniqueName('subscribe-diff')
+
+  const ports = (instances) => instances.map((instance) => instance.port).sort()
+  const diffs = recorder((err, diff) => ({
+    added: ports(diff.added),
+    removed: ports(diff.removed),
+    modified: ports(diff.modified),
+    current: ports(diff.current),
+  }))
+  const sub = await client.subscribeDiff(serviceName, 'TEST_GROUP', null, diffs)
+
+  await client.registerInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port: 8080 })
+  await diffs.received(1)
+  await client.registerInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port: 8081 })
+  await diffs.received(2)
+  await client.registerInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port: 8080, weight: 2 })
+  await diffs.received(3)
+  await client.deregisterInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port: 8081 })
+
+  t.deepEqual(await diffs.received(4), [
+    { added: [8080], removed: [], modified: [], current: [8080] },
+    { added: [8081], removed: [], modified: [], current: [8080, 8081] },
+    { added: [], removed: [], modified: [8080], current: [8080, 8081] },
+    { added: [], removed: [8081], modified: [], current: [8080] },
+  ])
+
+  sub.dispose()
+})
+
+nacosTest('diffs of quick instance changes replay to the final instances', async (t) => {
+  const client = namingClient()
+  const serviceName = uniqueName('subscribe-diff-quick')
+
+  const ports = (instances) => instances.map((instance) => instance.port).sort()
+  const diffs = recorder()
+  const sub = await client.subscribeDiff(serviceName, 'TEST_GROUP', null, diffs)
+
+  for (const port of [8080, 8081, 8082, 8083]) {
+    await client.registerInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port })
+  }
+  await client.deregisterInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port: 8081 })
+  await client.deregisterInstance(serviceName, 'TEST_GROUP', { ip: '127.0.0.1', port: 8083 })
+
+  const received = await eventually(
+    () => diffs.values,
+    (values) => ports(values.at(-1)?.current ?? []).join() === '8080,8082',
+  )
+  const replayed = new Set()
+  for (const diff of received) {
+    diff.added.forEach((instance) => replayed.add(instance.port))
+    diff.removed.forEach((instance) => replayed.delete(instance.port))
+  }
+  t.deepEqual([...replayed].sort(), [8080, 8082])
+  t.deepEqual(ports(received.at(-1).current), [8080, 8082])
+
+  sub.dispose()
+})
+
+nacosTest('change listener receives previous config and changed keys', async (t) => {
+  const client = configClient()
+  const dataId = `${uniqueName('change-listener')}.properties`
+  await client.publishConfig(dataId, 'TEST_GROUP', 'a=1\nb=2')
+
+  const events = recorder()
+  const sub = await client.addChangeListener(dataId, 'TEST_GROUP', events)
+  await listenSettled(dataId)
+
+  await client.publishConfig(dataId, 'TEST_GROUP', 'a=1\nb=3\nc=4')
+  const [event] = await events.received(1)
+
+  t.is(event.previous.content, 'a=1\nb=2')
+  t.is(event.current.content, 'a=1\nb=3\nc=4')
+  t.deepEqual(event.changes, [
+    { key: 'b', changeType: 'modified', previous: '2', current: '3' },
+    { key: 'c', changeType: 'added', current: '4' },
+  ])
+
+  sub.dispose()
+})
+
+nacosTest('get config parsed by its format', async (t) => {
+  const client = configClient()
+  const name = uniqueName('parsed')
+  await client.publishConfig(`${name}.json`, 'TEST_GROUP', '{"db":{"host":"127.0.0.1","port":3306}}')
+  await client.publishConfig(`${name}.yaml`, 'TEST_GROUP', 'db:\n  host: 127.0.0.1\n  port: 3306\n')
+  await client.publishConfig(`${name}.properties`, 'TEST_GROUP', 'db.host=127.0.0.1\ndb.port=3306')
+  await client.publishConfig(`${name}.toml`, 'TEST_GROUP', '[db]\nhost = "127.0.0.1"\nport = 3306\n')
+
+  const expected = { db: { host: '127.0.0.1', port: 3306 } }
+  t.deepEqual(await client.getConfigParsed(`${name}.json`, 'TEST_GROUP'), expected)
+  t.deepEqual(await client.getConfigParsed(`${name}.yaml`, 'TEST_GROUP'), expected)
+  t.deepEqual(await client.getConfigParsed(`${name}.toml`, 'TEST_GROUP'), expected)
+  t.deepEqual(await client.getConfigParsed(`${name}.properties`, 'TEST_GROUP'), {
+    'db.host': '127.0.0.1',
+    'db.port': '3306',
+  })
+
+  await client.publishConfig(`${name}.json`, 'TEST_GROUP', '{\n  "db": {,\n}')
+  const err = await t.throwsAsync(client.getConfigParsed(`${name}.json`, 'TEST_GROUP'))
+  t.like(err, { code: 'ConfigParseError', dataId: `${name}.json`, format: 'json', line: 2, column: 10 })
+})
+
+nacosTest('parsed listener receives parsed config or parse error', async (t) => {
+  const client = configClient()
+  const dataId = `${uniqueName('parsed-listener')}.json`
+  await client.publishConfig(dataId, 'TEST_GROUP', '{"a":1}')
+
+  const received = recorder((err, value, resp) => ({ code: err?.code, value, content: resp.content }))
+  const sub = await client.addParsedListener(dataId, 'TEST_GROUP', received)
+  await listenSettled(dataId)
+
+  await client.publishConfig(dataId, 'TEST_GROUP', '{"a":2}')
+  await received.received(1)
+  await client.publishConfig(dataId, 'TEST_GROUP', '{"a":')
+
+  t.deepEqual(await received.received(2), [
+    { code: undefined, value: { a: 2 }, content: '{"a":2}' },
+    { code: 'ConfigParseError', value: undefined, content: '{"a":' },
+  ])
+
+  sub.dispose()
+})
+
+nacosTest('publish config cas', async (t) => {
+  const client = configClient()
+  const dataId = uniqueName('cas')
+  await client.publishConfig(dataId, 'TEST_GROUP', 'v1')
+  const { md5 } = await client.getConfigResp(dataId, 'TEST_GROUP')
+
+  t.true(await client.publishConfigCas(dataId, 'TEST_GROUP', 'v2', md5))
+  t.is(await client.getConfig(dataId, 'TEST_GROUP'), 'v2')
+
+  // md5 of server has moved on from v1
+  const err = await t.throwsAsync(client.publishConfigCas(dataId, 'TEST_GROUP', 'v3', md5))
+  t.like(err, { code: 'ConfigCasConflict', dataId, group: 'TEST_GROUP' })
+  t.is(await client.getConfig(dataId, 'TEST_GROUP'), 'v2')
+})
+
+// always against the mock, whose failure of cas publish has the same message as nacos server,
+// so that a change of the message which tells the conflict is caught
+mockTest('publish config cas conflict from mock nacos', async (t) => {
+  const other = await startMockNacos()
+  const client = configClient({ serverAddr: other.serverAddr })
+  await client.publishConfig('mock-cas', 'TEST_GROUP', 'v1')
+  const { md5 } = await client.getConfigResp('mock-cas', 'TEST_GROUP')
+  t.true(await client.publishConfigCas('mock-cas', 'TEST_GROUP', 'v2', md5))
+
+  const err = await t.throwsAsync(client.publishConfigCas('mock-cas', 'TEST_GROUP', 'v3', md5))
+  t.like(err, { code: 'ConfigCasConflict', dataId: 'mock-cas', group: 'TEST_GROUP' })
+  t.regex(err.message, /Cas publish fail/)
+  t.is(await client.getConfig('mock-cas', 'TEST_GROUP'), 'v2')
+  other.close()
+})
+
+nacosTest('publish config with options', async (t) => {
+  const published = []
+  const client = configClient({}, (err, req, resp) => {
+    if (req != null) {
+      published.push(req.encryptedDataKey)
+    }
+    return [req, resp]
+  })
+  const dataId = uniqueName('publish-options')
+
+  t.true(
+    await client.publishConfig(dataId, 'TEST_GROUP', '{"a":1}', {
+      type: 'json',
+      tags: ['team-a', 'flags'],
+      desc: 'feature flags',
+      appName: 'deploy-tool',
+      encryptedDataKey: 'data-key',
+    }),
+  )
+  t.deepEqual(published, ['data-key'])
+
+  const resp = await client.getConfigResp(dataId, 'TEST_GROUP')
+  t.is(resp.contentType, 'json')
+  t.deepEqual(await client.getConfigParsed(dataId, 'TEST_GROUP'), { a: 1 })
+})
+
+nacosTest('publish and stop beta config', async (t) => {
+  const client = configClient({ configCheckBeta: true })
+  const dataId = uniqueName('beta')
+  await client.publishConfig(dataId, 'TEST_GROUP', 'stable')
+  t.false((await client.getConfigResp(dataId, 'TEST_GROUP')).isBeta)
+  // not checked by default
+  const unchecked = configClient()
+  t.is((await unchecked.getConfigResp(dataId, 'TEST_GROUP')).isBeta, undefined)
+
+  const received = recorder((err, resp) => ({ content: resp.content, isBeta: resp.isBeta }))
+  const sub = await client.addListener(dataId, 'TEST_GROUP', received)
+  await listenSettled(dataId)
+
+  // this host is one of the betaIps, whichever ip the client reports
+  const localIps = Object.values(os.networkInterfaces())
+    .flat()
+    .filter((iface) => iface.family === 'IPv4' || iface.family === 4)
+    .map((iface) => iface.address)
+  t.true(await client.publishBetaConfig(dataId, 'TEST_GROUP', 'gray', ['127.0.0.1', ...localIps]))
+  await received.received(1)
+  t.like(await client.getConfigResp(dataId, 'TEST_GROUP'), { content: 'gray', isBeta: true })
+
+  t.true(await client.stopBetaConfig(dataId, 'TEST_GROUP'))
+  await received.received(2)
+  t.like(await client.getConfigResp(dataId, 'TEST_GROUP'), { content: 'stable', isBeta: false })
+
+  t.deepEqual(received.values, [
+    { content: 'gray', isBeta: true },
+    { content: 'stable', isBeta: false },
+  ])
+  sub.dispose()
+})
+
+nacosTest('get config or default if not found', async (t) => {
+  const client = configClient()
+  const dataId = uniqueName('not-found')
+
+  const err = await t.throwsAsync(client.getConfig(dataId, 'TEST_GROUP'))
+  t.like(err, { code: 'ConfigNotFound', dataId, group: 'TEST_GROUP' })
+  await t.throwsAsync(client.getConfigResp(dataId, 'TEST_GROUP'), { code: 'ConfigNotFound' })
+  t.is(await client.getConfigOrDefault(dataId, 'TEST_GROUP', 'fallback'), 'fallback')
+
+  await client.publishConfig(dataId, 'TEST_GROUP', 'v1')
+  t.is(await client.getConfigOrDefault(dataId, 'TEST_GROUP', 'fallback'), 'v1')
+})
+
+nacosTest('get configs in batch with per-entry error', async (t) => {
+  const client = configClient()
+  const prefix = uniqueName('batch')
+  await client.publishConfig(`${prefix}-a`, 'TEST_GROUP', 'a')
+  await client.publishConfig(`${prefix}-b`, 'TEST_GROUP', 'b')
+
+  const keys = Array.from({ length: 20 }, (_, i) => ({ dataId: `${prefix}-${i}`, group: 'TEST_GROUP' }))
+  const results = await client.getConfigs([
+    { dataId: `${prefix}-a`, group: 'TEST_GROUP' },
+    { dataId: `${prefix}-missing`, group: 'TEST_GROUP' },
+    { dataId: `${prefix}-b`, group: 'TEST_GROUP' },
+    ...keys,
+  ])
+  t.is(results[`${prefix}-a`].TEST_GROUP.resp.content, 'a')
+  t.is(results[`${prefix}-b`].TEST_GROUP.resp.content, 'b')
+  t.like(results[`${prefix}-missing`].TEST_GROUP, { resp: undefined, error: { code: 'ConfigNotFound' } })
+  // more keys than got at the same time
+  t.is(Object.keys(results).length, 23)
+  t.is(results[`${prefix}-19`].TEST_GROUP.error.code, 'ConfigNotFound')
+})
+
+nacosTest('compose layers into a live merged config', async (t) => {
+  const client = configClient()
+  const prefix = uniqueName('compose')
+  await client.publishConfig(`${prefix}-common.yaml`, 'TEST_GROUP', 'db:\n  host: common\n  port: 3306\nlog: info')
+  await client.publishConfig(`${prefix}-service.yaml`, 'TEST_GROUP', 'db:\n  host: service')
+
+  const events = recorder((err, event) => (err ? err.code : event))
+  const composed = await client.compose(
+    [
+      { dataId: `${prefix}-common.yaml`, group: 'TEST_GROUP' },
+      { dataId: `${prefix}-service.yaml`, group: 'TEST_GROUP' },
+      { dataId: `${prefix}-service-prod.yaml`, group: 'TEST_GROUP' },
+    ],
+    events,
+  )
+  t.deepEqual(composed.value, { db: { host: 'service', port: 3306 }, log: 'info' })
+  await listenSettled(`${prefix}-common.yaml`)
+  await listenSettled(`${prefix}-service.yaml`)
+
+  await client.publishConfig(`${prefix}-service.yaml`, 'TEST_GROUP', 'db:\n  host: service\n  port: 3307')
+  const [event] = await events.received(1)
+  t.deepEqual(composed.value, { db: { host: 'service', port: 3307 }, log: 'info' })
+  t.deepEqual(event.value, composed.value)
+  t.is(event.layer.dataId, `${prefix}-service.yaml`)
+
+  // a broken layer is reported, the last merged config is kept
+  await client.publishConfig(`${prefix}-service.yaml`, 'TEST_GROUP', 'db: [')
+  t.is((await events.received(2))[1], 'ConfigParseError')
+  t.deepEqual(composed.value, { db: { host: 'service', port: 3307 }, log: 'info' })
+
+  composed.dispose()
+  t.false(composed.active)
+  // another listener tells when the change is notified
+  const witness = recorder((err, resp) => resp.content)
+  const sub = await client.addListener(`${prefix}-common.yaml`, 'TEST_GROUP', witness)
+  await listenSettled(`${prefix}-common.yaml`)
+  await client.publishConfig(`${prefix}-common.yaml`, 'TEST_GROUP', 'log: debug')
+  await witness.received(1)
+  t.is(events.values.length, 2)
+  sub.dispose()
+})
+
+nacosTest('expand placeholders of config', async (t) => {
+  const prefix = uniqueName('placeholder')
+  const source = `${prefix}-db.properties`
+  process.env.NACOS_TEST_REGION = 'cn-east'
+  const client = configClient({
+    configPlaceholders: {
+      sources: [{ dataId: source, group: 'TEST_GROUP' }],
+      variables: { APP: 'demo' },
+    },
+  })
+  await client.publishConfig(
+    source,
+    'TEST_GROUP',
+    'db.host=10.0.0.1\ndb.url=jdbc://${db.host}/${APP}\ncycle.a=${cycle.b}\ncycle.b=${cycle.a}',
+  )
+  await client.publishConfig(`${prefix}-app`, 'TEST_GROUP', 'url=${db.url}\nregion=${env:NACOS_TEST_REGION}\nmissing=${nope}')
+  await client.publishConfig(`${prefix}-cycle`, 'TEST_GROUP', 'a=${cycle.a}')
+
+  t.is(
+    await client.getConfig(`${prefix}-app`, 'TEST_GROUP'),
+    'url=jdbc://10.0.0.1/demo\nregion=cn-east\nmissing=${nope}',
+  )
+  const err = await t.throwsAsync(client.getConfig(`${prefix}-cycle`, 'TEST_GROUP'))
+  t.like(err, { code: 'ConfigPlaceholderCycle', dataId: `${prefix}-cycle` })
+  // placeholders which grow exponentially fail fast instead of using up the memory
+  const doubled = Array.from({ length: 40 }, (_, i) => `grow${i}=\${grow${i + 1}}\${grow${i + 1}}`).join('\n')
+  await client.publishConfig(`${prefix}-grow.properties`, 'TEST_GROUP', `${doubled}\ngrow40=0123456789`)
+  const growClient = configClient({ configPlaceholders: { sources: [{ dataId: `${prefix}-grow.properties`, group: 'TEST_GROUP' }] } })
+  await client.publishConfig(`${prefix}-big`, 'TEST_GROUP', 'big=${grow0}')
+  t.like(await t.throwsAsync(growClient.getConfig(`${prefix}-big`, 'TEST_GROUP')), { code: 'ConfigPlaceholderLimit' })
+  const nested = Array.from({ length: 40 }, (_, i) => [`n${i}`, `\${n${i + 1}}`])
+  const deepClient = configClient({ configPlaceholders: { variables: Object.fromEntries(nested) } })
+  await client.publishConfig(`${prefix}-deep`, 'TEST_GROUP', 'deep=${n0}')
+  t.like(await t.throwsAsync(deepClient.getConfig(`${prefix}-deep`, 'TEST_GROUP')), { code: 'ConfigPlaceholderLimit' })
+  // the same placeholder referred more than once is expanded once, in bounds
+  const small = Array.from({ length: 10 }, (_, i) => [`s${i}`, `\${s${i + 1}}\${s${i + 1}}`])
+  const smallClient = configClient({ configPlaceholders: { variables: { ...Object.fromEntries(small), s10: 'x' } } })
+  t.is(await smallClient.getConfig(`${prefix}-deep`, 'TEST_GROUP'), 'deep=${n0}')
+  await client.publishConfig(`${prefix}-small`, 'TEST_GROUP', 'small=${s0}')
+  t.is(await smallClient.getConfig(`${prefix}-small`, 'TEST_GROUP'), `small=${'x'.repeat(1024)}`)
+
+  // the change whose placeholders are in cycle is skipped, not delivered unexpanded
+  const cycleReceived = recorder((err, resp) => resp.content)
+  const cycleSub = await client.addListener(`${prefix}-cycle`, 'TEST_GROUP', cycleReceived)
+  // the listener of another client without placeholders tells when the skipped one is notified
+  const plainReceived = recorder((err, resp) => resp.content)
+  const plainSub = await configClient().addListener(`${prefix}-cycle`, 'TEST_GROUP', plainReceived)
+  await listenSettled(`${prefix}-cycle`, 2)
+  await client.publishConfig(`${prefix}-cycle`, 'TEST_GROUP', 'a=${cycle.a}\nb=2')
+  await plainReceived.received(1)
+  await client.publishConfig(`${prefix}-cycle`, 'TEST_GROUP', 'a=1')
+  t.deepEqual(await cycleReceived.received(1), ['a=1'])
+  cycleSub.dispose()
+  plainSub.dispose()
+
+  // a change of the source re-triggers the listener of config which refers to it
+  const received = recorder((err, resp) => resp.content)
+  const sub = await client.addListener(`${prefix}-app`, 'TEST_GROUP', received)
+  await listenSettled(`${prefix}-app`)
+  await client.publishConfig(source, 'TEST_GROUP', 'db.host=10.0.0.2\ndb.url=jdbc://${db.host}/${APP}')
+  t.deepEqual(await received.received(1), ['url=jdbc://10.0.0.2/demo\nregion=cn-east\nmissing=${nope}'])
+
+  sub.dispose()
+})
+
+nacosTest('validate config by schema', async (t) => {
+  const dataId = `${uniqueName('schema')}.json`
+  const client = configClient()
+  // another client without schema, to publish the invalid config
+  const other = configClient()
+  client.registerSchema(dataId, 'TEST_GROUP', {
+    type: 'object',
+    properties: { port: { type: 'integer', minimum: 1 } },
+    required: ['port'],
+  })
+  t.throws(() => client.registerSchema(dataId, 'TEST_GROUP', { type: 'nope' }))
+
+  const err = await t.throwsAsync(client.publishConfig(dataId, 'TEST_GROUP', '{"port":"80"}'))
+  t.like(err, { code: 'ConfigSchemaInvalid', dataId, group: 'TEST_GROUP' })
+  t.true(err.errors.length > 0)
+  t.true(await client.publishConfig(dataId, 'TEST_GROUP', '{"port":80}'))
+  t.is(await client.getConfig(dataId, 'TEST_GROUP'), '{"port":80}')
+
+  const invalids = recorder()
+  client.onInvalidConfig(invalids)
+  const received = recorder((err, resp) => resp.content)
+  const sub = await client.addListener(dataId, 'TEST_GROUP', received)
+  await listenSettled(dataId)
+  await other.publishConfig(dataId, 'TEST_GROUP', '{"port":0}')
+  const [invalid] = await invalids.received(1)
+  await other.publishConfig(dataId, 'TEST_GROUP', '{"port":8080}')
+
+  t.deepEqual(await received.received(1), ['{"port":8080}'])
+  t.is(invalid.invalid.content, '{"port":0}')
+  t.is(invalid.current.content, '{"port":80}')
+  t.true(invalid.errors.length > 0)
+
+  // the last valid one is kept as current
+  await other.publishConfig(dataId, 'TEST_GROUP', '{}')
+  await invalids.received(2)
+  t.is(await client.getConfig(dataId, 'TEST_GROUP'), '{"port":8080}')
+  t.true(client.unregisterSchema(dataId, 'TEST_GROUP'))
+  t.false(client.unregisterSchema(dataId, 'TEST_GROUP'))
+  t.is(await client.getConfig(dataId, 'TEST_GROUP'), '{}')
+
+  // checked with the placeholders expanded, the same as the config received
+  const expanding = configClient({
+    configPlaceholders: { variables: { PORT: '80' } },
+  })
+  expanding.registerSchema(dataId, 'TEST_GROUP', {
+    type: 'object',
+    properties: { port: { type: 'integer' } },
+    required: ['port'],
+  })
+  t.true(await expanding.publishConfig(dataId, 'TEST_GROUP', '{"port":${PORT}}'))
+  t.is(await expanding.getConfig(dataId, 'TEST_GROUP'), '{"port":80}')
+  await t.throwsAsync(expanding.publishConfig(dataId, 'TEST_GROUP', '{"port":"${PORT}"}'), {
+    code: 'ConfigSchemaInvalid',
+  })
+
+  sub.dispose()
+  client.onInvalidConfig(null)
+})
+
+// AES/ECB/PKCS5Padding of the AES encryption plugin of nacos, by the utf8 of key, 16, 24 or 32 bytes
+const aesEcb = (encrypt, key, data) => {
+  const keyBytes = Buffer.from(key)
+  const algorithm = `aes-${keyBytes.length * 8}-ecb`
+  if (encrypt) {
+    const cipher = crypto.createCipheriv(algorithm, keyBytes, null)
+    return Buffer.concat([cipher.update(data, 'utf8'), cipher.final()]).toString('base64')
+  }
+  const decipher = crypto.createDecipheriv(algorithm, keyBytes, null)
+  return Buffer.concat([decipher.update(data, 'base64'), decipher.final()]).toString('utf8')
+}
+
+nacosTest('encrypt and decrypt cipher config', async (t) => {
+  const dataId = `${uniqueName('cipher-aes')}.properties`
+  const secretKey = 'nacos6b31e19f931a7603ae5473250b4'
+  const key = Buffer.from(secretKey).toString('base64')
+  const client = configClient({ configCipher: { key } })
+  const plain = configClient()
+  t.throws(() => configClient({ configCipher: { key: 'c2hvcnQ=' } }))
+
+  t.true(await client.publishConfig(dataId, 'TEST_GROUP', 'password=s3cret'))
+  t.is(await client.getConfig(dataId, 'TEST_GROUP'), 'password=s3cret')
+  // the server only has the ciphertext
+  const encrypted = await plain.getConfig(dataId, 'TEST_GROUP')
+  t.false(encrypted.includes('s3cret'))
+
+  const requested = []
+  const provided = configClient({
+    configCipher: {
+      keyProvider: async (err, req) => {
+        requested.push(req.dataId)
+        return key
+      },
+    },
+  })
+  t.is(await provided.getConfig(dataId, 'TEST_GROUP'), 'password=s3cret')
+  t.deepEqual(requested, [dataId])
+  // the format is the one of the AES plugin of nacos: the data key is base64 of random bytes, whose utf8 is the key
+  const [stored] = (await plain.exportConfigs('', { groups: ['TEST_GROUP'], dataIdPattern: dataId })).configs
+  const dataKey = aesEcb(false, secretKey, stored.encryptedDataKey)
+  t.is(Buffer.from(dataKey, 'base64').length, 16)
+  t.is(aesEcb(false, dataKey, stored.content), 'password=s3cret')
+  const pluginId = `${uniqueName('cipher-aes')}.properties`
+  const pluginKey = crypto.randomBytes(16).toString('base64')
+  await plain.publishConfig(pluginId, 'TEST_GROUP', aesEcb(true, pluginKey, 'password=plugin'), {
+    encryptedDataKey: aesEcb(true, secretKey, pluginKey),
+  })
+  t.is(await client.getConfig(pluginId, 'TEST_GROUP'), 'password=plugin')
+  // the key of provider is checked the same as the static one
+  const shortProvided = configClient({ configCipher: { keyProvider: async () => 'c2hvcnQ=' } })
+  const err = await t.throwsAsync(shortProvided.publishConfig(dataId, 'TEST_GROUP', 'password=short'))
+  t.like(err, { code: 'ConfigFilterError' })
+  t.true(err.message.includes('key provider returns invalid key: invalid key length 5, expected 16, 24 or 32'))
+
+  const received = recorder((err, resp) => resp.content)
+  const sub = await provided.addListener(dataId, 'TEST_GROUP', received)
+  await listenSettled(dataId)
+  await client.publishConfig(dataId, 'TEST_GROUP', 'password=changed')
+  t.deepEqual(await received.received(1), ['password=changed'])
+
+  sub.dispose()
+})
+
+nacosTest('config filter failure fails closed or passes through', async (t) => {
+  const prefix = uniqueName('filter-fail')
+  let broken = true
+  const failed = recorder((resp) => resp.content)
+  const filter = (err, req, resp) => {
+    if (broken && req != null) {
+      throw new Error('can not encrypt')
+    }
+    if (broken && resp != null) {
+      failed(resp)
+      return null
+    }
+    return [req, resp]
+  }
+  const client = configClient({}, filter)
+  const passThrough = configClient({ configFilterPassThrough: true }, filter)
+  const plain = configClient()
+
+  const err = await t.throwsAsync(client.publishConfig(`${prefix}-a`, 'TEST_GROUP', 'a=1'))
+  t.like(err, { code: 'ConfigFilterError', dataId: `${prefix}-a`, group: 'TEST_GROUP' })
+  // nothing is published if it fails closed
+  t.like(await t.throwsAsync(plain.getConfig(`${prefix}-a`, 'TEST_GROUP')), { code: 'ConfigNotFound' })
+  t.true(await passThrough.publishConfig(`${prefix}-a`, 'TEST_GROUP', 'a=1'))
+
+  t.like(await t.throwsAsync(client.getConfig(`${prefix}-a`, 'TEST_GROUP')), { code: 'ConfigFilterError' })
+  t.is(await passThrough.getConfig(`${prefix}-a`, 'TEST_GROUP'), 'a=1')
+
+  // nor by the filters following the failed one
+  const chained = configClient({}, [
+    {
+      onRequest: () => {
+        throw new Error('can not encrypt')
+      },
+    },
+    { onRequest: (req) => ({ ...req, content: req.content || 'cleared' }) },
+  ])
+  t.like(await t.throwsAsync(chained.publishConfig(`${prefix}-b`, 'TEST_GROUP', 'b=1')), { code: 'ConfigFilterError' })
+  t.like(await t.throwsAsync(plain.getConfig(`${prefix}-b`, 'TEST_GROUP')), { code: 'ConfigNotFound' })
+
+  // the listeners skip the change which fails to filter
+  broken = false
+  const received = recorder((err, resp) => resp.content)
+  const sub = await client.addListener(`${prefix}-a`, 'TEST_GROUP', received)
+  await listenSettled(`${prefix}-a`)
+  broken = true
+  await plain.publishConfig(`${prefix}-a`, 'TEST_GROUP', 'a=2')
+  await eventually(() => failed.values, (values) => values.includes('a=2'))
+  broken = false
+  await plain.publishConfig(`${prefix}-a`, 'TEST_GROUP', 'a=3')
+  t.deepEqual(await received.received(1), ['a=3'])
+
+  sub.dispose()
+})
+
+test('config filter failure skips the listener while got concurrently', async (t) => {
+  const dir = fs.mkdtempSync(path.join(os.tmpdir(), 'nacos-filter-'))
+  t.teardown(() => fs.rmSync(dir, { recursive: true, force: true }))
+  const groupDir = path.join(dir, 'public', 'DEFAULT_GROUP')
+  fs.mkdirSync(groupDir, { recursive: true })
+  fs.writeFileSync(path.join(groupDir, 'app'), 'a=1')
+
+  let broken = false
+  let getting = null
+  const client = configClient({ serverAddr: `file://${dir}` }, [
+    {
+      onResponse: (resp) => {
+        if (broken) {
+          throw new Error('can not decrypt')
+        }
+        return resp
+      },
+    },
+    // the listener is notified after this, got meanwhile
+    {
+      onResponse: async (resp) => {
+        if (broken && getting == null) {
+          getting = client.getConfig('app', 'DEFAULT_GROUP').catch((err) => err.code)
+          await getting
+        }
+        return resp
+      },
+    },
+  ])
+  const received = recorder((err, resp) => resp.content)
+  await client.addListener('app', 'DEFAULT_GROUP', received)
+
+  broken = true
+  fs.writeFileSync(path.join(groupDir, 'app'), 'a=2')
+  await eventually(() => getting, (got) => got != null)
+  t.is(await getting, 'ConfigFilterError')
+  await sleep(100)
+  broken = false
+  fs.writeFileSync(path.join(groupDir, 'app'), 'a=3')
+  t.deepEqual(await received.received(1), ['a=3'])
+})
+
+nacosTest('chain of config filters matched by dataId and group', async (t) => {
+  const prefix = uniqueName('filter-chain')
+  const audited = []
+  const client = configClient({}, [
+    {
+      dataId: `${prefix}-*.b64`,
+      filter: (err, req, resp) => {
+        if (req != null) {
+          req.content = Buffer.from(req.content).toString('base64')
+        }
+        if (resp != null) {
+          resp.content = Buffer.from(resp.content, 'base64').toString()
+        }
+        return [req, resp]
+      },
+    },
+    {
+      filter: (err, req, resp) => {
+        audited.push(`${req != null ? 'req' : 'resp'}:${(req ?? resp).content}`)
+        return [req, resp]
+      },
+    },
+    {
+      group: 'OTHER_*',
+      filter: () => {
+        throw new Error('never called')
+      },
+    },
+  ])
+  t.throws(() => configClient({}, [{ dataId: 'a[', filter: () => {} }]))
+  const plain = configClient()
+
+  t.true(await client.publishConfig(`${prefix}-a.b64`, 'TEST_GROUP', 'secret'))
+  t.true(await client.publishConfig(`${prefix}-b`, 'TEST_GROUP', 'plain'))
+  t.is(await plain.getConfig(`${prefix}-a.b64`, 'TEST_GROUP'), Buffer.from('secret').toString('base64'))
+  t.is(await client.getConfig(`${prefix}-a.b64`, 'TEST_GROUP'), 'secret')
+  t.is(await client.getConfig(`${prefix}-b`, 'TEST_GROUP'), 'plain')
+  // the filters are called in order, the later one sees what the former returns
+  t.deepEqual(audited, ['req:c2VjcmV0', 'req:plain', 'resp:secret', 'resp:plain'])
+})
+
+nacosTest('async config filter with timeout', async (t) => {
+  const dataId = uniqueName('filter-async')
+  let hang = false
+  const client = configClient({ configFilterTimeoutMs: 500 }, async (err, req, resp) => {
+    if (hang) {
+      return new Promise(() => {})
+    }
+    await sleep(50)
+    if (req != null) {
+      req.content = req.content.toUpperCase()
+    }
+    return [req, resp]
+  })
+
+  t.true(await client.publishConfig(dataId, 'TEST_GROUP', 'value'))
+  t.is(await client.getConfig(dataId, 'TEST_GROUP'), 'VALUE')
+
+  hang = true
+  const err = await t.throwsAsync(client.publishConfig(dataId, 'TEST_GROUP', 'other'))
+  t.like(err, { code: 'ConfigFilterError', dataId })
+  t.true(err.message.includes('timed out'))
+  t.like(await t.throwsAsync(client.getConfig(dataId, 'TEST_GROUP')), { code: 'ConfigFilterError' })
+})
+
+nacosTest('config filter with onRequest and onResponse hooks', async (t) => {
+  const prefix = uniqueName('filter-hooks')
+  const requested = []
+  const client = configClient({}, {
+    onResponse: async (resp) => ({ ...resp, content: resp.content.split('').reverse().join('') }),
+  })
+  const auditor = configClient({}, [
+    {
+      dataId: `${prefix}-*`,
+      onRequest: (req) => {
+        requested.push(req.content)
+        return req
+      },
+    },
+    { dataId: `${prefix}-forgot`, onRequest: () => {} },
+  ])
+  t.throws(() => configClient({}, { filter: () => {}, onRequest: (req) => req }))
+  t.throws(() => configClient({}, { dataId: 'a' }))
+
+  // only onResponse, the publish passes as it is
+  t.true(await client.publishConfig(`${prefix}-a`, 'TEST_GROUP', 'abc'))
+  t.is(await client.getConfig(`${prefix}-a`, 'TEST_GROUP'), 'cba')
+  t.is(await auditor.getConfig(`${prefix}-a`, 'TEST_GROUP'), 'abc')
+
+  t.true(await auditor.publishConfig(`${prefix}-b`, 'TEST_GROUP', 'audited'))
+  t.deepEqual(requested, ['audited'])
+  // forgetting the return value fails the publish instead of panic
+  const err = await t.throwsAsync(auditor.publishConfig(`${prefix}-forgot`, 'TEST_GROUP', 'x'))
+  t.like(err, { code: 'ConfigFilterError' })
+
+  // the hooks are called as methods of the entry
+  const tagged = configClient({}, {
+    tag: 'tagged:',
+    onRequest(req) {
+      return { ...req, content: this.tag + req.content }
+    },
+    onResponse(resp) {
+      return { ...resp, content: resp.content.slice(this.tag.length) }
+    },
+  })
+  t.true(await tagged.publishConfig(`${prefix}-c`, 'TEST_GROUP', 'c'))
+  t.is(await auditor.getConfig(`${prefix}-c`, 'TEST_GROUP'), 'tagged:c')
+  t.is(await tagged.getConfig(`${prefix}-c`, 'TEST_GROUP'), 'c')
+})
+
+nacosTest('failover to config snapshots', async (t) => {
+  const dataId = uniqueName('snapshot')
+  const configSnapshotDir = fs.mkdtempSync(path.join(os.tmpdir(), 'nacos-snapshot-'))
+  t.teardown(() => fs.rmSync(configSnapshotDir, { recursive: true, force: true }))
+  const client = configClient({ configSnapshotDir })
+  await client.publishConfig(dataId, 'TEST_GROUP', 'v1')
+  const resp = await client.getConfigResp(dataId, 'TEST_GROUP')
+  t.false(resp.stale)
+  t.true(fs.existsSync(path.join(configSnapshotDir, 'public', 'TEST_GROUP', dataId)))
+
+  // the server can not be reached
+  const unreachable = configClient({ serverAddr: '127.0.0.1:1', configSnapshotDir })
+  const stale = await unreachable.getConfigResp(dataId, 'TEST_GROUP')
+  t.like(stale, { content: 'v1', md5: resp.md5, stale: true })
+
+  // always read from the snapshots, even if the server has changed
+  const always = configClient({ configSnapshotDir, configFailoverMode: 'always' })
+  await always.publishConfig(dataId, 'TEST_GROUP', 'v2')
+  t.like(await always.getConfigResp(dataId, 'TEST_GROUP'), { content: 'v1', stale: true })
+  t.is(await client.getConfig(dataId, 'TEST_GROUP'), 'v2')
+  t.like(await always.getConfigResp(dataId, 'TEST_GROUP'), { content: 'v2', stale: true })
+  // and the snapshot served is refreshed by the server in background
+  await always.publishConfig(dataId, 'TEST_GROUP', 'v3')
+  t.like(await always.getConfigResp(dataId, 'TEST_GROUP'), { content: 'v2', stale: true })
+  const refreshed = await eventually(
+    () => always.getConfigResp(dataId, 'TEST_GROUP'),
+    (resp) => resp.content === 'v3',
+  )
+  t.like(refreshed, { content: 'v3', stale: true })
+
+  // the snapshot is removed with the config, the one not refreshed in background by the client above
+  const removedId = `removed-${dataId}`
+  await client.publishConfig(removedId, 'TEST_GROUP', 'v1')
+  await client.getConfig(removedId, 'TEST_GROUP')
+  t.true(fs.existsSync(path.join(configSnapshotDir, 'public', 'TEST_GROUP', removedId)))
+  await client.removeConfig(removedId, 'TEST_GROUP')
+  await t.throwsAsync(client.getConfig(removedId, 'TEST_GROUP'))
+  t.false(fs.existsSync(path.join(configSnapshotDir, 'public', 'TEST_GROUP', removedId)))
+  t.throws(() => configClient({ configSnapshotDir, configFailoverMode: 'nope' }))
+
+  // the snapshot of cipher config is encrypted, and decrypted when it is read
+  const cipherId = `cipher-aes-${dataId}`
+  const configCipher = { key: Buffer.alloc(32, 7).toString('base64') }
+  const cipher = configClient({ configSnapshotDir, configCipher })
+  await cipher.publishConfig(cipherId, 'TEST_GROUP', 'password=s3cret')
+  t.is(await cipher.getConfig(cipherId, 'TEST_GROUP'), 'password=s3cret')
+  const snapshot = fs.readFileSync(path.join(configSnapshotDir, 'public', 'TEST_GROUP', cipherId), 'utf8')
+  t.false(snapshot.includes('s3cret'))
+  const cipherUnreachable = configClient({ serverAddr: '127.0.0.1:1', configSnapshotDir, configCipher })
+  t.like(await cipherUnreachable.getConfigResp(cipherId, 'TEST_GROUP'), { content: 'password=s3cret', stale: true })
+  t.is(await unreachable.getConfig(cipherId, 'TEST_GROUP'), JSON.parse(snapshot).content)
+})
+
+test('serve configs from file serverAddr', async (t) => {
+  const dir = fs.mkdtempSync(path.join(os.tmpdir(), 'nacos-file-'))
+  t.teardown(() => fs.rmSync(dir, { recursive: true, force: true }))
+  const groupDir = path.join(dir, 'public', 'DEFAULT_GROUP')
+  fs.mkdirSync(groupDir, { recursive: true })
+  fs.writeFileSync(path.join(groupDir, 'app.json'), '{"port":8080}')
+
+  const client = configClient({ serverAddr: `file://${dir}` })
+  t.is(await client.getConfig('app.json', 'DEFAULT_GROUP'), '{"port":8080}')
+  t.deepEqual(await client.getConfigParsed('app.json', 'DEFAULT_GROUP'), { port: 8080 })
+  await t.throwsAsync(client.getConfig('missing.json', 'DEFAULT_GROUP'), { code: 'ConfigNotFound' })
+
+  const received = recorder((err, resp) => resp.content)
+  await client.addListener('app.json', 'DEFAULT_GROUP', received)
+  // changed on disk
+  fs.writeFileSync(path.join(groupDir, 'app.json'), '{"port":8081}')
+  t.deepEqual(await received.received(1), ['{"port":8081}'])
+  // the file being written is not read half-written
+  fs.writeFileSync(path.join(groupDir, 'app.json'), '{"port":')
+  await sleep(100)
+  fs.appendFileSync(path.join(groupDir, 'app.json'), '8083}')
+  t.deepEqual(await received.received(2), ['{"port":8081}', '{"port":8083}'])
+
+  // published and removed to disk
+  const resp = await client.getConfigResp('app.json', 'DEFAULT_GROUP')
+  await t.throwsAsync(client.publishConfigCas('app.json', 'DEFAULT_GROUP', '{"port":1}', 'stale-md5'), {
+    code: 'ConfigCasConflict',
+  })
+  t.true(await client.publishConfigCas('app.json', 'DEFAULT_GROUP', '{"port":8082}', resp.md5))
+  t.is(fs.readFileSync(path.join(groupDir, 'app.json'), 'utf8'), '{"port":8082}')
+  t.true(await client.removeConfig('app.json', 'DEFAULT_GROUP'))
+  t.false(fs.existsSync(path.join(groupDir, 'app.json')))
+  t.deepEqual(await received.received(4), ['{"port":8081}', '{"port":8083}', '{"port":8082}', ''])
+
+  // the dataIds named as the meta or reserved dir of the store are configs as well
+  t.true(await client.publishConfig('x', 'DEFAULT_GROUP', '{"x":1}', { type: 'json' }))
+  t.true(await client.publishConfig('.x.meta', 'DEFAULT_GROUP', 'meta-like'))
+  t.true(await client.publishConfig('.nacos', 'DEFAULT_GROUP', 'reserved-like'))
+  t.like(await client.getConfigResp('x', 'DEFAULT_GROUP'), { content: '{"x":1}', contentType: 'json' })
+  t.is(await client.getConfig('.x.meta', 'DEFAULT_GROUP'), 'meta-like')
+  t.is(await client.getConfig('.nacos', 'DEFAULT_GROUP'), 'reserved-like')
+  const bundle = await client.exportConfigs('')
+  t.deepEqual(
+    bundle.configs.map((config) => config.dataId),
+    ['.nacos', '.x.meta', 'x'],
+  )
+})
+
+nacosTest('export and import configs', async (t) => {
+  const group = uniqueName('EXPORT')
+  const source = configClient()
+  await source.publishConfig('app.yaml', group, 'port: 8080', { type: 'yaml' })
+  await source.publishConfig('db.yaml', group, 'url: db', { type: 'yaml' })
+  await source.publishConfig('app.json', group, '{}')
+
+  const bundle = await source.exportConfigs('', { groups: [group], dataIdPattern: '*.yaml' })
+  t.is(bundle.namespace, '')
+  t.deepEqual(
+    bundle.configs.map((config) => [config.dataId, config.type, config.content]),
+    [
+      ['app.yaml', 'yaml', 'port: 8080'],
+      ['db.yaml', 'yaml', 'url: db'],
+    ],
+  )
+  t.is(bundle.configs[0].md5, (await source.getConfigResp('app.yaml', group)).md5)
+  await t.throwsAsync(source.exportConfigs('', { dataIdPattern: '[' }))
+
+  const target = configClient({ namespace: uniqueName('import') })
+  const dryRun = await target.importConfigs(bundle, { dryRun: true })
+  t.like(dryRun, { dryRun: true, aborted: false })
+  t.deepEqual(
+    dryRun.items.map((item) => item.action),
+    ['create', 'create'],
+  )
+  await t.throwsAsync(target.getConfig('app.yaml', group))
+
+  const imported = await target.importConfigs(bundle)
+  t.deepEqual(
+    imported.items.map((item) => [item.dataId, item.action, item.error]),
+    [
+      ['app.yaml', 'create', undefined],
+      ['db.yaml', 'create', undefined],
+    ],
+  )
+  t.like(await target.getConfigResp('app.yaml', group), { content: 'port: 8080', contentType: 'yaml' })
+  t.deepEqual(
+    (await target.importConfigs(bundle)).items.map((item) => item.action),
+    ['unchanged', 'unchanged'],
+  )
+
+  // the config changed in target conflicts
+  await target.publishConfig('app.yaml', group, 'port: 9090', { type: 'yaml' })
+  const aborted = await target.importConfigs(bundle)
+  t.like(aborted, { aborted: true })
+  t.deepEqual(
+    aborted.items.map((item) => item.action),
+    ['conflict', 'unchanged'],
+  )
+  t.is(await target.getConfig('app.yaml', group), 'port: 9090')
+  t.is((await target.importConfigs(bundle, { conflict: 'skip' })).items[0].action, 'skip')
+  t.is(await target.getConfig('app.yaml', group), 'port: 9090')
+  t.is((await target.importConfigs(bundle, { conflict: 'overwrite' })).items[0].action, 'overwrite')
+  t.is(await target.getConfig('app.yaml', group), 'port: 8080')
+
+  // the item tampered with is skipped
+  const tampered = { ...bundle, configs: [{ ...bundle.configs[1], content: 'url: other' }] }
+  const report = await target.importConfigs(tampered, { conflict: 'overwrite' })
+  t.like(report.items[0], { action: 'skip', error: { code: 'ConfigBundleInvalid' } })
+  t.is(await target.getConfig('db.yaml', group), 'url: db')
+  await t.throwsAsync(target.importConfigs(bundle, { conflict: 'nope' }))
+
+  // imported as it is, without the config filters and the schema
+  const namespace = uniqueName('import-filtered')
+  const filtered = configClient({ namespace }, {
+    onRequest: (req) => ({ ...req, content: req.content.toUpperCase() }),
+  })
+  filtered.registerSchema('app.yaml', group, { type: 'object', required: ['host'] })
+  t.deepEqual(
+    (await filtered.importConfigs(bundle)).items.map((item) => [item.action, item.error]),
+    [
+      ['create', undefined],
+      ['create', undefined],
+    ],
+  )
+  const plainFiltered = configClient({ namespace })
+  t.is(await plainFiltered.getConfig('app.yaml', group), 'port: 8080')
+})
+
+nacosTest('export and import cipher configs', async (t) => {
+  const group = uniqueName('EXPORT_CIPHER')
+  const configCipher = { key: Buffer.alloc(32, 7).toString('base64') }
+  const source = configClient({ configCipher })
+  await source.publishConfig('cipher-aes-db.properties', group, 'password=s3cret')
+  // the encryptedDataKey of publish options does not skip the cipher
+  await source.publishConfig('cipher-aes-other.properties', group, 'password=other', { encryptedDataKey: 'plain' })
+  const plain = configClient()
+  t.false((await plain.getConfig('cipher-aes-other.properties', group)).includes('other'))
+  t.is(await source.getConfig('cipher-aes-other.properties', group), 'password=other')
+
+  const bundle = await source.exportConfigs('', { groups: [group] })
+  t.false(bundle.configs.some((config) => config.content.includes('s3cret')))
+  const namespace = uniqueName('import-cipher')
+  const target = configClient({ namespace, configCipher })
+  t.deepEqual(
+    (await target.importConfigs(bundle)).items.map((item) => [item.action, item.error]),
+    [
+      ['create', undefined],
+      ['create', undefined],
+    ],
+  )
+  // the content encrypted is imported as it is, so it is decrypted by the same key
+  t.is(await target.getConfig('cipher-aes-db.properties', group), 'password=s3cret')
+  const plainTarget = configClient({ namespace })
+  t.is(await plainTarget.getConfig('cipher-aes-db.properties', group), bundle.configs[0].content)
+})
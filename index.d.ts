@@ -4,8 +4,17 @@
 /* auto-generated by NAPI-RS */
 
 export declare function sum(a: number, b: number): number
+export interface NacosComposedChangeEvent {
+  /** The merged config of all layers */
+  value: any
+  /** The layer changed */
+  layer: NacosConfigResponse
+}
 export interface ClientOptions {
-  /** Server Addr, e.g. address:port[,address:port],...] */
+  /**
+   * Server Addr, e.g. address:port[,address:port],...]
+   * or `file:///path/to/configs` for NacosConfigClient, which serves the configs from the dir without nacos server
+   */
   serverAddr: string
   /** Namespace/Tenant */
   namespace: string
@@ -27,6 +36,159 @@ export interface ClientOptions {
   namingLoadCacheAtStart?: boolean
   /** config load_cache_at_start, default false */
   configLoadCacheAtStart?: boolean
+  /**
+   * config `isBeta` checked by the http open api of nacos server, default false that `isBeta` is absent.
+   * It costs a request for each config got or notified, and it is only a hint, as nacos-sdk does not tell it:
+   * the server judges it by the ip of http request, which may not be the one of grpc, e.g. behind a proxy
+   */
+  configCheckBeta?: boolean
+  /** config placeholders `${name}` expanded by variables, env vars and keys of other configs, default disabled */
+  configPlaceholders?: PlaceholderOptions
+  /**
+   * config built-in cipher of the configs whose dataId starts with `cipher-aes-`, default disabled.
+   * The same format as the AES encryption plugin of nacos, so the configs are shared with the java clients of the same key
+   */
+  configCipher?: ConfigCipherOptions
+  /**
+   * config content passes through as it is if config filters fail, default false that fails closed:
+   * getConfig and publishConfig reject with code 'ConfigFilterError', and the listeners skip the change
+   */
+  configFilterPassThrough?: boolean
+  /** config filters (and the key provider of cipher) fail if they take longer, in milliseconds, default 3000 */
+  configFilterTimeoutMs?: number
+  /**
+   * config snapshots are written to the dir atomically on every successful get and notify, default disabled.
+   * They are the contents before config filters, e.g. encrypted, which are filtered again when read
+   */
+  configSnapshotDir?: string
+  /**
+   * config read from the snapshots, 'off', 'onError' (the server can not be reached in 3s) or 'always', default 'onError'.
+   * The config read from the snapshot is `stale`, and in 'always' the snapshot is refreshed by the server in background
+   */
+  configFailoverMode?: 'off' | 'onError' | 'always'
+}
+export interface ExportConfigsOptions {
+  /** Only the configs of the groups, default all groups */
+  groups?: Array<string>
+  /** Only the configs whose dataId matches the glob pattern, e.g. `*.yaml`, default all */
+  dataIdPattern?: string
+}
+/** Configs exported by `exportConfigs`, which can be saved as json and imported by `importConfigs`. */
+export interface NacosConfigBundle {
+  /** Namespace/Tenant exported from */
+  namespace: string
+  /** The configs, in the order of group and dataId */
+  configs: Array<NacosConfigBundleItem>
+}
+export interface NacosConfigBundleItem {
+  /** DataId */
+  dataId: string
+  /** Group */
+  group: string
+  /** Content as stored, e.g. encrypted for the configs of cipher */
+  content: string
+  /** Content's Type; e.g. json,properties,xml,html,text,yaml */
+  type: string
+  /** Content's md5 */
+  md5: string
+  /** Content's Encrypted Data Key, absent if the content is not encrypted */
+  encryptedDataKey?: string
+}
+export interface ImportConfigsOptions {
+  /**
+   * How to import the config which exists with other content, 'skip', 'overwrite',
+   * or 'abort' that nothing is imported if any config conflicts, default 'abort'
+   */
+  conflict?: 'skip' | 'overwrite' | 'abort'
+  /** Only report what would change, nothing is published, default false */
+  dryRun?: boolean
+}
+export const enum ConfigImportAction {
+  /** The config does not exist, published */
+  Create = 'create',
+  /** The config exists with other content, published by conflict 'overwrite' */
+  Overwrite = 'overwrite',
+  /** Not published, the config exists with other content by conflict 'skip', or the item is invalid */
+  Skip = 'skip',
+  /** The config exists with the same content, not published */
+  Unchanged = 'unchanged',
+  /** The config exists with other content, so nothing is imported by conflict 'abort' */
+  Conflict = 'conflict'
+}
+export interface NacosConfigImportItem {
+  /** DataId */
+  dataId: string
+  /** Group */
+  group: string
+  /** What is done, or would be done by dry run */
+  action: ConfigImportAction
+  /** Why it failed to publish, or the item is invalid, absent if not failed */
+  error?: NacosConfigError
+}
+export interface NacosConfigImportReport {
+  /** Whether it is dry run, nothing is published */
+  dryRun: boolean
+  /** Whether nothing is imported, as some configs conflict by conflict 'abort' */
+  aborted: boolean
+  /** The configs of bundle in order */
+  items: Array<NacosConfigImportItem>
+}
+export interface ConfigCipherOptions {
+  /** Static master key in base64, 16, 24 or 32 bytes, e.g. the secret key of the AES plugin of nacos in base64 */
+  key?: string
+  /** Provide the master key in base64 of the config, prior to the static key, it times out as config filters */
+  keyProvider?: (err: Error | null, req: NacosCipherKeyReq) => string | Promise<string>
+}
+/** Which config the master key is provided for. */
+export interface NacosCipherKeyReq {
+  /** DataId */
+  dataId: string
+  /** Group */
+  group: string
+  /** Namespace/Tenant */
+  namespace: string
+}
+export interface NacosConfigKey {
+  /** DataId */
+  dataId: string
+  /** Group */
+  group: string
+}
+export interface NacosConfigResult {
+  /** DataId */
+  dataId: string
+  /** Group */
+  group: string
+  /** The config, absent if it failed to get */
+  resp?: NacosConfigResponse
+  /** Why it failed to get, absent if got */
+  error?: NacosConfigError
+}
+export interface NacosConfigError {
+  /** Code of error, e.g. 'ConfigNotFound', all listed in the README, absent if it is not known */
+  code?: string
+  /** Message of error */
+  message: string
+}
+export interface NacosInvalidConfigEvent {
+  /** The config which does not match the schema, held back from listeners */
+  invalid: NacosConfigResponse
+  /** Why it does not match */
+  errors: Array<string>
+  /** The last valid config which is kept as current, absent if none */
+  current?: NacosConfigResponse
+}
+export interface PublishConfigOptions {
+  /** Content's Type; e.g. json,properties,xml,html,text,yaml */
+  type?: string
+  /** Tags */
+  tags?: Array<string>
+  /** Description */
+  desc?: string
+  /** AppName */
+  appName?: string
+  /** Content's Encrypted Data Key */
+  encryptedDataKey?: string
 }
 export interface NacosConfigResponse {
   /** Namespace/Tenant */
@@ -41,6 +203,43 @@ export interface NacosConfigResponse {
   contentType: string
   /** Content's md5 */
   md5: string
+  /**
+   * Whether the content is the beta (gray) one for this client, a hint checked by another request of the http open api,
+   * which nacos server judges by the ip of http request, not always the one of grpc, e.g. behind a proxy.
+   * Absent if `configCheckBeta` is not enabled, it can not be checked, or the config changed meanwhile
+   */
+  isBeta?: boolean
+  /** Whether the content is read from the local snapshot in failover mode, so it may be stale */
+  stale: boolean
+}
+export interface NacosConfigChangeEvent {
+  /** The previous config, absent if it not existed */
+  previous?: NacosConfigResponse
+  /** The current config */
+  current: NacosConfigResponse
+  /** Changed keys, only if content's type (or dataId's extension) is properties, json, yaml or toml, and both can be parsed */
+  changes?: Array<NacosConfigItemChange>
+}
+export const enum ConfigChangeType {
+  Added = 'added',
+  Removed = 'removed',
+  Modified = 'modified'
+}
+export interface NacosConfigItemChange {
+  /** Key, nested key of json and yaml is flatten like `a.b[0].c` */
+  key: string
+  /** Type of change, one of 'added', 'removed', 'modified' */
+  changeType: ConfigChangeType
+  /** Value before, absent if added */
+  previous?: string
+  /** Value after, absent if removed */
+  current?: string
+}
+export interface NacosConfigWatchResult {
+  /** Whether the watcher closed */
+  done: boolean
+  /** The config change, absent if done */
+  value?: NacosConfigResponse
 }
 export interface NacosServiceInstance {
   /** Instance Id */
@@ -64,7 +263,50 @@ export interface NacosServiceInstance {
   /** Metadata, default '{}' */
   metadata?: Record<string, string>
 }
+export interface NacosInstancesDiff {
+  /** Instances which not in previous */
+  added: Array<NacosServiceInstance>
+  /** Instances which not in current */
+  removed: Array<NacosServiceInstance>
+  /** Instances whose weight, healthy, enabled or metadata changed */
+  modified: Array<NacosServiceInstance>
+  /** All current instances */
+  current: Array<NacosServiceInstance>
+}
+export interface NacosNamingWatchResult {
+  /** Whether the watcher closed */
+  done: boolean
+  /** The instances snapshot, absent if done */
+  value?: Array<NacosServiceInstance>
+}
 /** ConfigReq for [`ConfigFilter`] */
+export interface PlaceholderOptions {
+  /**
+   * Configs whose keys can be referred, e.g. `${db.host}`, nested keys are flatten like `a.b[0].c`.
+   * The later config overrides the former
+   */
+  sources?: Array<NacosConfigKey>
+  /** Variables can be referred, prior to the keys of sources */
+  variables?: Record<string, string>
+  /** Whether `${env:NAME}` refers to the env var NAME, default true */
+  env?: boolean
+}
+/**
+ * A config filter of the chain, which only filters the configs matched by dataId and group.
+ * It is either `filter` for both request and response, or `onRequest` and/or `onResponse`.
+ */
+export interface NacosConfigFilterEntry {
+  /** The filter func, called with `(err, req, resp)` and returns `[req, resp]` or a Promise of it */
+  filter?: (err: Error | null, req?: NacosConfigReq | undefined | null, resp?: NacosConfigResp | undefined | null) => any
+  /** Filter the request to publish, returns the req or a Promise of it */
+  onRequest?: (req: NacosConfigReq) => NacosConfigReq | Promise<NacosConfigReq>
+  /** Filter the response got or listened, returns the resp or a Promise of it */
+  onResponse?: (resp: NacosConfigResp) => NacosConfigResp | Promise<NacosConfigResp>
+  /** Glob of dataId, e.g. `cipher-*`, default all */
+  dataId?: string
+  /** Glob of group, e.g. `DEFAULT_*`, default all */
+  group?: string
+}
 export interface NacosConfigReq {
   /** DataId */
   dataId: string
@@ -90,41 +332,171 @@ export interface NacosConfigResp {
   /** Content's Encrypted Data Key. */
   encryptedDataKey: string
 }
+/** What to do when a change comes but the watch queue is full. */
+export const enum OverflowPolicy {
+  /** Drop the oldest change in queue, keep the newer ones. */
+  DropOldest = 'drop-oldest',
+  /** Keep the latest change only, every change replaces the one in queue whether it is full or not. */
+  CoalesceLatest = 'coalesce-latest'
+}
+export interface WatchOptions {
+  /** Max changes buffered before consumed, default 16 */
+  capacity?: number
+  /** What to do when the buffer is full, default 'drop-oldest'. 'coalesce-latest' buffers the newest change only */
+  overflow?: OverflowPolicy
+}
 /** Client api of Nacos Config. */
+/**
+ * Config composed of layers by `compose`, keeps live until disposed.
+ * It is a disposable object as well, e.g. `using composed = await client.compose(...)`.
+ */
+export class NacosComposedConfig {
+  /** The merged config of all layers. */
+  get value(): any
+  /** Whether the listeners of layers are still active, false after disposed. */
+  get active(): boolean
+  /** Remove the listeners of all layers, call it more than once is fine. */
+  dispose(): void
+}
 export class NacosConfigClient {
   /** Build a Config Client. */
-  constructor(clientOptions: ClientOptions, configFilter?: (err: Error | null, arg0?: NacosConfigReq | undefined | null, arg1?: NacosConfigResp | undefined | null) => any | undefined | null)
+  constructor(clientOptions: ClientOptions, configFilter?: ((err: Error | null, req?: NacosConfigReq | undefined | null, resp?: NacosConfigResp | undefined | null) => any) | NacosConfigFilterEntry | Array<NacosConfigFilterEntry> | undefined | null)
   /**
    * Get config's content.
-   * If it fails, pay attention to err
+   * If it fails, pay attention to err, e.g. code 'ConfigNotFound' if the config does not exist
    */
   getConfig(dataId: string, group: string): Promise<string>
+  /**
+   * Get config's content, or the default value if the config does not exist.
+   * If it fails, pay attention to err, e.g. the server can not be connected
+   */
+  getConfigOrDefault(dataId: string, group: string, defaultValue: string): Promise<string>
   /**
    * Get NacosConfigResponse.
-   * If it fails, pay attention to err
+   * If it fails, pay attention to err, e.g. code 'ConfigNotFound' if the config does not exist
    */
   getConfigResp(dataId: string, group: string): Promise<NacosConfigResponse>
   /**
-   * Publish config.
+   * Get many configs concurrently (at most 8 at a time), the results are keyed by dataId and then group,
+   * e.g. `results[dataId][group]`.
+   * A config failed to get has error instead of resp, e.g. code 'ConfigNotFound' if it does not exist.
+   */
+  getConfigs(keys: Array<NacosConfigKey>): Promise<Record<string, Record<string, NacosConfigResult>>>
+  /**
+   * Get config's content parsed by its format, one of json, yaml, properties or toml.
+   * Format is the content's type, or the extension of dataId if the type is not structured.
+   * If it fails, pay attention to err, e.g. code 'ConfigParseError' if the content can not be parsed
+   */
+  getConfigParsed(dataId: string, group: string): Promise<any>
+  /**
+   * Publish config, with options of type, tags, desc, appName and encryptedDataKey.
+   * If it fails, pay attention to err, e.g. code 'ConfigSchemaInvalid' if the content does not match the schema
+   */
+  publishConfig(dataId: string, group: string, content: string, options?: PublishConfigOptions | undefined | null): Promise<boolean>
+  /**
+   * Publish config only if the md5 of config in server is the expected one, i.e. compare and swap.
+   * If it fails, pay attention to err, e.g. code 'ConfigCasConflict' if the md5 of server has changed
+   */
+  publishConfigCas(dataId: string, group: string, content: string, expectedMd5: string): Promise<boolean>
+  /**
+   * Publish beta (gray) config, only the clients of betaIps get it, others still get the stable one.
+   * If it fails, pay attention to err, e.g. code 'ConfigSchemaInvalid' if the content does not match the schema
+   */
+  publishBetaConfig(dataId: string, group: string, content: string, betaIps: Array<string>): Promise<boolean>
+  /**
+   * Stop beta (gray) config, so that the clients of betaIps get the stable one again.
+   * It is done by the http open api of nacos server.
    * If it fails, pay attention to err
    */
-  publishConfig(dataId: string, group: string, content: string): Promise<boolean>
+  stopBetaConfig(dataId: string, group: string): Promise<boolean>
+  /**
+   * Register the JSON Schema of config, which its content parsed by format (the same as `getConfigParsed`) must match.
+   * The content does not match is rejected by publish, and held back from listeners (reported to `onInvalidConfig`),
+   * the last valid config is kept as current. Register again to replace the schema.
+   * If it fails, pay attention to err, e.g. the schema is not valid
+   */
+  registerSchema(dataId: string, group: string, schema: object | boolean): void
+  /** Unregister the JSON Schema of config, return false if not registered. */
+  unregisterSchema(dataId: string, group: string): boolean
+  /**
+   * Set the callback func, which is called once the config received does not match its schema.
+   * Only one callback func is kept, set it again to replace, or null to remove.
+   */
+  onInvalidConfig(listener?: ((err: Error | null, event: NacosInvalidConfigEvent) => any) | null): void
   /**
    * Remove config.
    * If it fails, pay attention to err
    */
   removeConfig(dataId: string, group: string): Promise<boolean>
+  /**
+   * Export the configs of namespace as a bundle, with the content as stored, e.g. encrypted for the configs of cipher.
+   * The configs are listed by the http open api of nacos server, or the dir of file serverAddr.
+   * If it fails, pay attention to err
+   */
+  exportConfigs(namespace: string, options?: ExportConfigsOptions | undefined | null): Promise<NacosConfigBundle>
+  /**
+   * Import the configs of bundle into the namespace of client, each is published as it is in the bundle,
+   * e.g. encrypted, without config filters and the schema.
+   * The config which does not exist is created, the one exists with the same md5 is unchanged,
+   * and the one exists with other content is imported by options.conflict.
+   * The config changed since it is planned is not published, with error code 'ConfigCasConflict'.
+   * Return the report of what is done, or would be done by dry run, with the error of each config failed to publish.
+   * If it fails, pay attention to err, e.g. the current configs can not be listed
+   */
+  importConfigs(bundle: NacosConfigBundle, options?: ImportConfigsOptions | undefined | null): Promise<NacosConfigImportReport>
   /**
    * Add NacosConfigChangeListener callback func, which listen the config change.
+   * Return a Subscription, dispose it to remove the listener.
    * If it fails, pay attention to err
    */
-  addListener(dataId: string, group: string, listener: (err: Error | null, arg: NacosConfigResponse) => any): Promise<void>
+  addListener(dataId: string, group: string, listener: (err: Error | null, arg: NacosConfigResponse) => any): Promise<Subscription>
   /**
-   * Remove NacosConfigChangeListener callback func, but noop....
-   * The logic is not implemented internally, and only APIs are provided as compatibility.
-   * Users maybe do not need it? Not removing the listener is not a big problem, Sorry!
+   * Add NacosConfigChangeListener callback func, which listen the config change parsed by its format,
+   * the same as `getConfigParsed`, err's code is 'ConfigParseError' if the content can not be parsed.
+   * Return a Subscription, dispose it (or `removeListener` the func) to remove the listener.
+   * If it fails, pay attention to err
+   */
+  addParsedListener(dataId: string, group: string, listener: (err: Error | null, value: any, resp: NacosConfigResponse) => any): Promise<Subscription>
+  /**
+   * Add NacosConfigChangeListener callback func, which listen the config change with the previous one,
+   * and the changed keys if content's type is properties, json or yaml.
+   * Return a Subscription, dispose it (or `removeListener` the func) to remove the listener.
+   * If it fails, pay attention to err
+   */
+  addChangeListener(dataId: string, group: string, listener: (err: Error | null, arg: NacosConfigChangeEvent) => any): Promise<Subscription>
+  /**
+   * Remove NacosConfigChangeListener callback func, the same func which passed to `addListener`.
+   * If the func was added more than once, only one of them is removed.
+   * If it fails, pay attention to err
    */
   removeListener(dataId: string, group: string, listener: (err: Error | null, arg: NacosConfigResponse) => any): Promise<void>
+  /**
+   * Watch the config change, return an async iterator, e.g. `for await (const resp of client.watch(dataId, group))`.
+   * Changes are buffered until consumed, see WatchOptions. Break out of the loop to remove the listener.
+   * If it fails, pay attention to err of the iterator
+   */
+  watch(dataId: string, group: string, options?: WatchOptions | undefined | null): NacosConfigWatcher
+  /**
+   * Compose the configs of layers into one, by deep merging their parsed contents (the same as `getConfigParsed`),
+   * the later layer overrides the former, e.g. `[common.yaml, service.yaml, service-prod.yaml]`.
+   * A layer which does not exist is seen as empty, and nacos-sdk does not notify its first publish.
+   * The composed config keeps live by listening to every layer, the listener is called with the merged config
+   * once any layer changes, or err if the layer can not be parsed.
+   * Dispose the returned NacosComposedConfig to remove the listeners.
+   * If it fails, pay attention to err, whose code is 'ConfigParseError' if a layer can not be parsed
+   */
+  compose(layers: Array<NacosConfigKey>, listener?: (err: Error | null, event: NacosComposedChangeEvent) => any | undefined | null): Promise<NacosComposedConfig>
+}
+/** Async iterator of the config change, returned by `watch`. */
+export class NacosConfigWatcher {
+  /**
+   * Wait for the next config change, done after the watcher closed.
+   * If it fails, pay attention to err
+   */
+  next(): Promise<NacosConfigWatchResult>
+  /** Close the watcher and remove its listener, `break` of `for await` calls it. */
+  return(): NacosConfigWatchResult
+  [Symbol.asyncIterator](): NacosConfigWatcher
 }
 /** Client api of Nacos Naming. */
 export class NacosNamingClient {
@@ -162,13 +534,50 @@ export class NacosNamingClient {
   selectOneHealthyInstance(serviceName: string, group: string, clusters?: Array<string> | undefined | null, subscribe?: boolean | true): Promise<NacosServiceInstance>
   /**
    * Add NacosNamingEventListener callback func, which listen the instance change.
+   * Return a Subscription, dispose it to remove the listener.
    * If it fails, pay attention to err
    */
-  subscribe(serviceName: string, group: string, clusters: Array<string> | undefined | null, listener: (err: Error | null, arg: Array<NacosServiceInstance>) => any): Promise<void>
+  subscribe(serviceName: string, group: string, clusters: Array<string> | undefined | null, listener: (err: Error | null, arg: Array<NacosServiceInstance>) => any): Promise<Subscription>
   /**
-   * Remove NacosNamingEventListener callback func, but noop....
-   * The logic is not implemented internally, and only APIs are provided as compatibility.
-   * Users maybe do not need it? Not removing the subscription is not a big problem, Sorry!
+   * Add NacosNamingEventListener callback func, which listen the instance change as diff,
+   * compared with the previous instances of this subscription, keyed by ip:port:cluster.
+   * Return a Subscription, dispose it (or `unSubscribe` the func) to remove the listener.
+   * If it fails, pay attention to err
+   */
+  subscribeDiff(serviceName: string, group: string, clusters: Array<string> | undefined | null, listener: (err: Error | null, arg: NacosInstancesDiff) => any): Promise<Subscription>
+  /**
+   * Remove NacosNamingEventListener callback func, the same func which passed to `subscribe`
+   * with the same service, group and clusters.
+   * If the func was subscribed more than once, only one of them is removed.
+   * If it fails, pay attention to err
    */
   unSubscribe(serviceName: string, group: string, clusters: Array<string> | undefined | null, listener: (err: Error | null, arg: Array<NacosServiceInstance>) => any): Promise<void>
+  /**
+   * Watch the instance change, return an async iterator of instances snapshot,
+   * e.g. `for await (const instances of client.watchInstances(serviceName, group))`.
+   * Only the newest snapshot is kept until consumed. Break out of the loop to unsubscribe.
+   * If it fails, pay attention to err of the iterator
+   */
+  watchInstances(serviceName: string, group: string, clusters?: Array<string> | undefined | null): NacosNamingWatcher
+}
+/** Async iterator of the instances snapshot, returned by `watchInstances`. */
+export class NacosNamingWatcher {
+  /**
+   * Wait for the next instances snapshot, done after the watcher closed.
+   * If it fails, pay attention to err
+   */
+  next(): Promise<NacosNamingWatchResult>
+  /** Close the watcher and unsubscribe, `break` of `for await` calls it. */
+  return(): NacosNamingWatchResult
+  [Symbol.asyncIterator](): NacosNamingWatcher
+}
+/**
+ * Handle of a listener which returned by `addListener` or `subscribe`, remove the listener on its own.
+ * It is a disposable object as well, e.g. `using sub = await client.addListener(...)`.
+ */
+export class Subscription {
+  /** Whether the listener is still active, false after disposed or removed. */
+  get active(): boolean
+  /** Remove the listener, call it more than once is fine. */
+  dispose(): void
 }
@@ -246,8 +246,17 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { sum, NacosConfigClient, NacosNamingClient } = nativeBinding
+const { sum, startMockNacos, MockNacos, NacosComposedConfig, NacosConfigClient, NacosConfigWatcher, NacosNamingClient, NacosNamingWatcher, Subscription, ConfigImportAction, OverflowPolicy, ConfigChangeType } = nativeBinding
 
 module.exports.sum = sum
+module.exports.startMockNacos = startMockNacos
+module.exports.MockNacos = MockNacos
+module.exports.NacosComposedConfig = NacosComposedConfig
 module.exports.NacosConfigClient = NacosConfigClient
+module.exports.NacosConfigWatcher = NacosConfigWatcher
 module.exports.NacosNamingClient = NacosNamingClient
+module.exports.NacosNamingWatcher = NacosNamingWatcher
+module.exports.Subscription = Subscription
+module.exports.ConfigImportAction = ConfigImportAction
+module.exports.OverflowPolicy = OverflowPolicy
+module.exports.ConfigChangeType = ConfigChangeType
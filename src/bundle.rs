@@ -0,0 +1,236 @@
+use napi::bindgen_prelude::*;
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[napi(object)]
+pub struct ExportConfigsOptions {
+  /// Only the configs of the groups, default all groups
+  pub groups: Option<Vec<String>>,
+  /// Only the configs whose dataId matches the glob pattern, e.g. `*.yaml`, default all
+  pub data_id_pattern: Option<String>,
+}
+
+/// Configs exported by `exportConfigs`, which can be saved as json and imported by `importConfigs`.
+#[napi(object)]
+pub struct NacosConfigBundle {
+  /// Namespace/Tenant exported from
+  pub namespace: String,
+  /// The configs, in the order of group and dataId
+  pub configs: Vec<NacosConfigBundleItem>,
+}
+
+#[napi(object)]
+pub struct NacosConfigBundleItem {
+  /// DataId
+  pub data_id: String,
+  /// Group
+  pub group: String,
+  /// Content as stored, e.g. encrypted for the configs of cipher
+  pub content: String,
+  /// Content's Type; e.g. json,properties,xml,html,text,yaml
+  #[napi(js_name = "type")]
+  pub content_type: String,
+  /// Content's md5
+  pub md5: String,
+  /// Content's Encrypted Data Key, absent if the content is not encrypted
+  pub encrypted_data_key: Option<String>,
+}
+
+#[napi(object)]
+pub struct ImportConfigsOptions {
+  /// How to import the config which exists with other content, 'skip', 'overwrite',
+  /// or 'abort' that nothing is imported if any config conflicts, default 'abort'
+  #[napi(ts_type = "'skip' | 'overwrite' | 'abort'")]
+  pub conflict: Option<String>,
+  /// Only report what would change, nothing is published, default false
+  pub dry_run: Option<bool>,
+}
+
+#[napi(string_enum = "lowercase")]
+#[derive(PartialEq)]
+pub enum ConfigImportAction {
+  /// The config does not exist, published
+  Create,
+  /// The config exists with other content, published by conflict 'overwrite'
+  Overwrite,
+  /// Not published, the config exists with other content by conflict 'skip', or the item is invalid
+  Skip,
+  /// The config exists with the same content, not published
+  Unchanged,
+  /// The config exists with other content, so nothing is imported by conflict 'abort'
+  Conflict,
+}
+
+#[napi(object)]
+pub struct NacosConfigImportItem {
+  /// DataId
+  pub data_id: String,
+  /// Group
+  pub group: String,
+  /// What is done, or would be done by dry run
+  pub action: ConfigImportAction,
+  /// Why it failed to publish, or the item is invalid, absent if not failed
+  pub error: Option<crate::NacosConfigError>,
+}
+
+#[napi(object)]
+pub struct NacosConfigImportReport {
+  /// Whether it is dry run, nothing is published
+  pub dry_run: bool,
+  /// Whether nothing is imported, as some configs conflict by conflict 'abort'
+  pub aborted: bool,
+  /// The configs of bundle in order
+  pub items: Vec<NacosConfigImportItem>,
+}
+
+/// How to import the config which exists with other content.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum ImportConflict {
+  Skip,
+  Overwrite,
+  Abort,
+}
+
+impl ImportConflict {
+  pub(crate) fn new(conflict: Option<&str>) -> Result<Self> {
+    match conflict {
+      Some("skip") => Ok(ImportConflict::Skip),
+      Some("overwrite") => Ok(ImportConflict::Overwrite),
+      None | Some("abort") => Ok(ImportConflict::Abort),
+      Some(other) => Err(Error::from_reason(format!(
+        "unsupported import conflict {other}, expected 'skip', 'overwrite' or 'abort'"
+      ))),
+    }
+  }
+}
+
+/// List the configs stored in namespace, by the http open api of nacos server, or the dir of file serverAddr.
+pub(crate) async fn list_configs(
+  inner: &crate::ConfigBackend,
+  open_api: Option<&crate::OpenApiClient>,
+  namespace: &str,
+) -> std::result::Result<Vec<NacosConfigBundleItem>, String> {
+  let open_api = match (inner, open_api) {
+    (crate::ConfigBackend::File(store), _) => {
+      return store
+        .list_configs(namespace)
+        .await
+        .map_err(|io_err| io_err.to_string());
+    }
+    (crate::ConfigBackend::Nacos(_), Some(open_api)) => open_api,
+    (crate::ConfigBackend::Nacos(_), None) => {
+      return Err("no open api to list configs".to_string());
+    }
+  };
+  let str_field = |item: &Value, name: &str| {
+    item
+      .get(name)
+      .and_then(Value::as_str)
+      .unwrap_or_default()
+      .to_string()
+  };
+  let mut configs: Vec<_> = open_api
+    .list_configs(namespace)
+    .await?
+    .iter()
+    .map(|item| {
+      let content = str_field(item, "content");
+      NacosConfigBundleItem {
+        data_id: str_field(item, "dataId"),
+        group: str_field(item, "group"),
+        md5: format!("{:x}", md5::compute(&content)),
+        content,
+        content_type: Some(str_field(item, "type"))
+          .filter(|content_type| !content_type.is_empty())
+          .unwrap_or("text".to_string()),
+        encrypted_data_key: Some(str_field(item, "encryptedDataKey")).filter(|key| !key.is_empty()),
+      }
+    })
+    .collect();
+  configs.sort_by(|a, b| (&a.group, &a.data_id).cmp(&(&b.group, &b.data_id)));
+  Ok(configs)
+}
+
+/// Keep the configs matched by the options of export.
+pub(crate) fn filter_exported(
+  configs: Vec<NacosConfigBundleItem>,
+  options: Option<ExportConfigsOptions>,
+) -> Result<Vec<NacosConfigBundleItem>> {
+  let Some(options) = options else {
+    return Ok(configs);
+  };
+  let data_id_matcher = options
+    .data_id_pattern
+    .map(|pattern| {
+      globset::Glob::new(&pattern)
+        .map(|glob| glob.compile_matcher())
+        .map_err(|err| Error::from_reason(format!("invalid dataIdPattern {pattern}: {err}")))
+    })
+    .transpose()?;
+  Ok(
+    configs
+      .into_iter()
+      .filter(|config| {
+        options
+          .groups
+          .as_ref()
+          .is_none_or(|groups| groups.contains(&config.group))
+          && data_id_matcher
+            .as_ref()
+            .is_none_or(|matcher| matcher.is_match(&config.data_id))
+      })
+      .collect(),
+  )
+}
+
+/// Plan the action of each config of bundle, by the md5 of the current configs of namespace.
+pub(crate) fn plan_import(
+  configs: &[NacosConfigBundleItem],
+  current: &[NacosConfigBundleItem],
+  conflict: ImportConflict,
+  dry_run: bool,
+) -> NacosConfigImportReport {
+  let current: HashMap<_, _> = current
+    .iter()
+    .map(|config| ((&config.data_id, &config.group), &config.md5))
+    .collect();
+  let items: Vec<_> = configs
+    .iter()
+    .map(|config| {
+      let (action, error) = if format!("{:x}", md5::compute(&config.content)) != config.md5 {
+        let error = crate::NacosConfigError {
+          code: Some("ConfigBundleInvalid".to_string()),
+          message: format!(
+            "md5 of content mismatches, dataId={}, group={}",
+            config.data_id, config.group
+          ),
+        };
+        (ConfigImportAction::Skip, Some(error))
+      } else {
+        let action = match current.get(&(&config.data_id, &config.group)) {
+          None => ConfigImportAction::Create,
+          Some(md5) if **md5 == config.md5 => ConfigImportAction::Unchanged,
+          Some(_) => match conflict {
+            ImportConflict::Skip => ConfigImportAction::Skip,
+            ImportConflict::Overwrite => ConfigImportAction::Overwrite,
+            ImportConflict::Abort => ConfigImportAction::Conflict,
+          },
+        };
+        (action, None)
+      };
+      NacosConfigImportItem {
+        data_id: config.data_id.clone(),
+        group: config.group.clone(),
+        action,
+        error,
+      }
+    })
+    .collect();
+  NacosConfigImportReport {
+    dry_run,
+    aborted: items
+      .iter()
+      .any(|item| item.action == ConfigImportAction::Conflict),
+    items,
+  }
+}
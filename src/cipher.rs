@@ -0,0 +1,270 @@
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
+use aes::{Aes128, Aes192, Aes256};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::*;
+
+/// DataId prefix of the config to be encrypted, in the `cipher-{algorithm}-` convention of nacos.
+/// The format is the one of the AES encryption plugin of nacos, so the configs are shared with the java clients.
+pub(crate) const CIPHER_PREFIX: &str = "cipher-aes-";
+/// Block size of AES.
+const BLOCK_LEN: usize = 16;
+/// Size of the random data key, before it is encoded in base64 as the nacos plugin.
+const DATA_KEY_LEN: usize = 16;
+
+#[napi(object, object_to_js = false)]
+pub struct ConfigCipherOptions {
+  /// Static master key in base64, 16, 24 or 32 bytes, e.g. the secret key of the AES plugin of nacos in base64
+  pub key: Option<String>,
+  /// Provide the master key in base64 of the config, prior to the static key, it times out as config filters
+  #[napi(ts_type = "(err: Error | null, req: NacosCipherKeyReq) => string | Promise<string>")]
+  pub key_provider: Option<ThreadsafeFunction<NacosCipherKeyReq>>,
+}
+
+/// Which config the master key is provided for.
+#[napi(object)]
+pub struct NacosCipherKeyReq {
+  /// DataId
+  pub data_id: String,
+  /// Group
+  pub group: String,
+  /// Namespace/Tenant
+  pub namespace: String,
+}
+
+/// AES/ECB/PKCS5Padding of the nacos plugin, by the key of 16, 24 or 32 bytes.
+enum AesEcb {
+  Aes128(Box<Aes128>),
+  Aes192(Box<Aes192>),
+  Aes256(Box<Aes256>),
+}
+
+impl AesEcb {
+  fn new(key: &[u8]) -> std::result::Result<Self, String> {
+    match key.len() {
+      16 => Ok(AesEcb::Aes128(Box::new(Aes128::new(key.into())))),
+      24 => Ok(AesEcb::Aes192(Box::new(Aes192::new(key.into())))),
+      32 => Ok(AesEcb::Aes256(Box::new(Aes256::new(key.into())))),
+      len => Err(format!("invalid key length {len}, expected 16, 24 or 32")),
+    }
+  }
+
+  /// Encrypt as base64 of the ciphertext padded.
+  fn encrypt(&self, plaintext: &[u8]) -> String {
+    let pad = BLOCK_LEN - plaintext.len() % BLOCK_LEN;
+    let mut data = plaintext.to_vec();
+    data.resize(plaintext.len() + pad, pad as u8);
+    for block in data.chunks_exact_mut(BLOCK_LEN) {
+      let block = GenericArray::from_mut_slice(block);
+      match self {
+        AesEcb::Aes128(aes) => aes.encrypt_block(block),
+        AesEcb::Aes192(aes) => aes.encrypt_block(block),
+        AesEcb::Aes256(aes) => aes.encrypt_block(block),
+      }
+    }
+    BASE64.encode(data)
+  }
+
+  /// Decrypt the base64 of the ciphertext padded.
+  fn decrypt(&self, ciphertext: &str) -> std::result::Result<Vec<u8>, String> {
+    let mut data = BASE64
+      .decode(ciphertext.trim())
+      .map_err(|err| format!("invalid base64: {err}"))?;
+    if data.is_empty() || data.len() % BLOCK_LEN != 0 {
+      return Err("invalid ciphertext length".to_string());
+    }
+    for block in data.chunks_exact_mut(BLOCK_LEN) {
+      let block = GenericArray::from_mut_slice(block);
+      match self {
+        AesEcb::Aes128(aes) => aes.decrypt_block(block),
+        AesEcb::Aes192(aes) => aes.decrypt_block(block),
+        AesEcb::Aes256(aes) => aes.decrypt_block(block),
+      }
+    }
+    let pad = data[data.len() - 1] as usize;
+    if pad == 0
+      || pad > BLOCK_LEN
+      || !data[data.len() - pad..]
+        .iter()
+        .all(|&byte| byte as usize == pad)
+    {
+      return Err("decrypt failed, the key or ciphertext is wrong".to_string());
+    }
+    data.truncate(data.len() - pad);
+    Ok(data)
+  }
+}
+
+/// Built-in cipher filter of the config whose dataId starts with `cipher-aes-`, encrypted by envelope encryption
+/// as the AES plugin of nacos: the content is encrypted by a random data key, i.e. base64 of 16 random bytes
+/// whose utf8 is the key, and the data key is encrypted by the master key as `encryptedDataKey`.
+pub(crate) struct ConfigCipherFilter {
+  key: Option<Vec<u8>>,
+  key_provider: Option<ThreadsafeFunction<NacosCipherKeyReq>>,
+  timeout: std::time::Duration,
+  failures: std::sync::Arc<crate::FilterFailures>,
+}
+
+impl ConfigCipherFilter {
+  pub(crate) fn new(
+    options: ConfigCipherOptions,
+    timeout: std::time::Duration,
+    failures: std::sync::Arc<crate::FilterFailures>,
+  ) -> Result<Self> {
+    let key = match options.key {
+      Some(key) => {
+        let key = BASE64
+          .decode(key)
+          .map_err(|err| Error::from_reason(format!("invalid config cipher key: {err}")))?;
+        AesEcb::new(&key)
+          .map_err(|err| Error::from_reason(format!("invalid config cipher key: {err}")))?;
+        Some(key)
+      }
+      None => None,
+    };
+    if key.is_none() && options.key_provider.is_none() {
+      return Err(Error::from_reason("config cipher needs key or keyProvider"));
+    }
+    Ok(ConfigCipherFilter {
+      key,
+      key_provider: options.key_provider,
+      timeout,
+      failures,
+    })
+  }
+
+  /// The master key of config, by the key provider if any, otherwise the static key.
+  async fn master_key(
+    &self,
+    data_id: &str,
+    group: &str,
+    namespace: &str,
+  ) -> std::result::Result<AesEcb, String> {
+    let Some(key_provider) = self.key_provider.as_ref() else {
+      return AesEcb::new(
+        self
+          .key
+          .as_deref()
+          .ok_or_else(|| "no master key".to_string())?,
+      );
+    };
+    let req = NacosCipherKeyReq {
+      data_id: data_id.to_string(),
+      group: group.to_string(),
+      namespace: namespace.to_string(),
+    };
+    let key: String = crate::await_js_call(key_provider.call_async(Ok(req)), self.timeout)
+      .await
+      .map_err(|err| format!("key provider failed: {err}"))?;
+    let key = BASE64
+      .decode(key)
+      .map_err(|err| format!("key provider returns invalid key: {err}"))?;
+    AesEcb::new(&key).map_err(|err| format!("key provider returns invalid key: {err}"))
+  }
+
+  async fn encrypt(
+    &self,
+    config_req: &nacos_sdk::api::plugin::ConfigReq,
+  ) -> std::result::Result<(String, String), String> {
+    let master_key = self
+      .master_key(
+        &config_req.data_id,
+        &config_req.group,
+        &config_req.namespace,
+      )
+      .await?;
+    let mut data_key = [0u8; DATA_KEY_LEN];
+    getrandom::getrandom(&mut data_key)
+      .map_err(|err| format!("generate data key failed: {err}"))?;
+    let data_key = BASE64.encode(data_key);
+    let content = AesEcb::new(data_key.as_bytes())?.encrypt(config_req.content.as_bytes());
+    let encrypted_data_key = master_key.encrypt(data_key.as_bytes());
+    Ok((content, encrypted_data_key))
+  }
+
+  async fn decrypt(
+    &self,
+    config_resp: &nacos_sdk::api::plugin::ConfigResp,
+  ) -> std::result::Result<String, String> {
+    let master_key = self
+      .master_key(
+        &config_resp.data_id,
+        &config_resp.group,
+        &config_resp.namespace,
+      )
+      .await?;
+    let data_key = master_key.decrypt(&config_resp.encrypted_data_key)?;
+    let content = AesEcb::new(&data_key)?.decrypt(&config_resp.content)?;
+    String::from_utf8(content).map_err(|err| format!("content is not utf8: {err}"))
+  }
+}
+
+#[async_trait::async_trait]
+impl nacos_sdk::api::plugin::ConfigFilter for ConfigCipherFilter {
+  async fn filter(
+    &self,
+    config_req: Option<&mut nacos_sdk::api::plugin::ConfigReq>,
+    config_resp: Option<&mut nacos_sdk::api::plugin::ConfigResp>,
+  ) {
+    if let Some(config_req) = config_req
+      && config_req.data_id.starts_with(CIPHER_PREFIX)
+    {
+      match self.encrypt(config_req).await {
+        Ok((content, encrypted_data_key)) => {
+          config_req.content = content;
+          config_req.encrypted_data_key = encrypted_data_key;
+        }
+        // never publish the plaintext even if it passes through, see `ConfigFilterChain::publish`
+        Err(err) => {
+          config_req.content.clear();
+          self.failures.fail(format!("config cipher failed: {err}"));
+        }
+      }
+    }
+
+    if let Some(config_resp) = config_resp
+      && config_resp.data_id.starts_with(CIPHER_PREFIX)
+      && !config_resp.content.is_empty()
+      && !config_resp.encrypted_data_key.is_empty()
+    {
+      match self.decrypt(config_resp).await {
+        Ok(content) => config_resp.content = content,
+        Err(err) => self
+          .failures
+          .response_failed(format!("config cipher failed: {err}")),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn aes_ecb_pads_as_pkcs5() {
+    // the block of FIPS-197, then the block of padding
+    let key: Vec<u8> = (0u8..16).collect();
+    let plaintext: Vec<u8> = (0u8..16).map(|i| i * 0x11).collect();
+    let aes = AesEcb::new(&key).unwrap();
+    let ciphertext = BASE64.decode(aes.encrypt(&plaintext)).unwrap();
+    assert_eq!(ciphertext.len(), 32);
+    assert_eq!(
+      ciphertext[..16],
+      [
+        0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4, 0xc5,
+        0x5a
+      ]
+    );
+    assert_eq!(aes.decrypt(&BASE64.encode(&ciphertext)).unwrap(), plaintext);
+
+    for key_len in [24, 32] {
+      let aes = AesEcb::new(&vec![7; key_len]).unwrap();
+      assert_eq!(aes.decrypt(&aes.encrypt(b"a=1")).unwrap(), b"a=1");
+    }
+    assert!(AesEcb::new(&[7; 20]).is_err());
+    assert!(aes.decrypt("YQ==").is_err());
+  }
+}
@@ -0,0 +1,159 @@
+use napi::{JsObject, JsUnknown, bindgen_prelude::*, threadsafe_function::*};
+use serde_json::Value;
+use std::sync::Mutex;
+
+/// Parsed contents of the layers, merged in order, the later layer overrides the former.
+pub(crate) struct ComposedLayers {
+  layers: Mutex<Vec<Layer>>,
+  func: Option<ThreadsafeFunction<crate::ParsedConfig, ErrorStrategy::Fatal>>,
+}
+
+impl ComposedLayers {
+  pub(crate) fn new(
+    size: usize,
+    func: Option<ThreadsafeFunction<crate::ParsedConfig, ErrorStrategy::Fatal>>,
+  ) -> Self {
+    ComposedLayers {
+      layers: Mutex::new(vec![Layer::default(); size]),
+      func,
+    }
+  }
+
+  /// Set the parsed content of layer fetched, None if the layer does not exist.
+  /// It is fetched after listened, so the change notified meanwhile is newer and kept.
+  pub(crate) fn set_fetched(&self, index: usize, value: Option<Value>) {
+    let mut layers = self.layers.lock().unwrap();
+    if !layers[index].changed {
+      layers[index].value = value;
+    }
+  }
+
+  /// Deep merge all layers, a layer not existed is seen as empty.
+  pub(crate) fn merged(&self) -> Value {
+    merge(&self.layers.lock().unwrap())
+  }
+
+  /// The layer changed, call the js callback func with the merged one.
+  /// If it can not be parsed, keep the last one of layer and call with the error.
+  /// The merged one is computed and the call is queued under the lock of layers,
+  /// so the calls are in the order of changes, each with the layer and the merged one of it.
+  pub(crate) fn update(&self, index: usize, conf_resp: crate::NacosConfigResponse) {
+    let parsed = if conf_resp.content.is_empty() {
+      // the config is removed
+      Ok(None)
+    } else {
+      crate::parse_config(&conf_resp).map(Some)
+    };
+    let mut layers = self.layers.lock().unwrap();
+    let merged = parsed.map(|value| {
+      layers[index] = Layer {
+        value,
+        changed: true,
+      };
+      merge(&layers)
+    });
+    if let Some(func) = self.func.as_ref() {
+      func.call((conf_resp, merged), ThreadsafeFunctionCallMode::NonBlocking);
+    }
+  }
+}
+
+/// Deep merge the layers in order, a layer not existed is seen as empty.
+fn merge(layers: &[Layer]) -> Value {
+  let mut merged = Value::Object(Default::default());
+  for value in layers.iter().filter_map(|layer| layer.value.as_ref()) {
+    crate::deep_merge(&mut merged, value.clone());
+  }
+  merged
+}
+
+/// A layer of [`ComposedLayers`].
+#[derive(Clone, Default)]
+struct Layer {
+  /// None if the layer does not exist.
+  value: Option<Value>,
+  /// Whether the change of layer has been notified.
+  changed: bool,
+}
+
+/// Build the js callback func of composed config, which called with `(err, NacosComposedChangeEvent)`.
+pub(crate) fn create_composed_listener(
+  listener: &JsFunction,
+) -> Result<ThreadsafeFunction<crate::ParsedConfig, ErrorStrategy::Fatal>> {
+  listener.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<crate::ParsedConfig>| {
+    let (layer, merged) = ctx.value;
+    let (err, event) = match merged {
+      Ok(value) => {
+        let event = NacosComposedChangeEvent { value, layer };
+        (ctx.env.get_null()?.into_unknown(), unsafe {
+          JsUnknown::from_napi_value(
+            ctx.env.raw(),
+            NacosComposedChangeEvent::to_napi_value(ctx.env.raw(), event)?,
+          )?
+        })
+      }
+      Err(parse_err) => (
+        parse_err.into_js_error(&ctx.env)?.into_unknown(),
+        ctx.env.get_undefined()?.into_unknown(),
+      ),
+    };
+    Ok(vec![err, event])
+  })
+}
+
+/// Config composed of layers by `compose`, keeps live until disposed.
+/// It is a disposable object as well, e.g. `using composed = await client.compose(...)`.
+#[napi]
+pub struct NacosComposedConfig {
+  composed: std::sync::Arc<ComposedLayers>,
+  targets: Vec<Box<dyn crate::SubscriptionTarget>>,
+}
+
+#[napi]
+impl NacosComposedConfig {
+  /// The merged config of all layers.
+  #[napi(getter, ts_return_type = "any")]
+  pub fn value(&self) -> Value {
+    self.composed.merged()
+  }
+
+  /// Whether the listeners of layers are still active, false after disposed.
+  #[napi(getter)]
+  pub fn active(&self) -> bool {
+    self.targets.iter().any(|target| target.is_active())
+  }
+
+  /// Remove the listeners of all layers, call it more than once is fine.
+  #[napi]
+  pub fn dispose(&self, env: Env) -> Result<()> {
+    for target in self.targets.iter() {
+      target.dispose(&env)?;
+    }
+    Ok(())
+  }
+}
+
+impl NacosComposedConfig {
+  pub(crate) fn new(
+    composed: std::sync::Arc<ComposedLayers>,
+    targets: Vec<Box<dyn crate::SubscriptionTarget>>,
+  ) -> Self {
+    NacosComposedConfig { composed, targets }
+  }
+
+  /// Into js object, and set `[Symbol.dispose]` if the node version supports it.
+  pub(crate) fn into_js_object(self, env: &Env) -> Result<JsObject> {
+    let mut object = self.into_instance(*env)?.as_object(*env);
+    crate::set_dispose_symbol(env, &mut object)?;
+    Ok(object)
+  }
+}
+
+#[napi(object)]
+pub struct NacosComposedChangeEvent {
+  /// The merged config of all layers
+  #[napi(ts_type = "any")]
+  pub value: Value,
+  /// The layer changed
+  pub layer: crate::NacosConfigResponse,
+}
@@ -1,12 +1,19 @@
 #![deny(clippy::all)]
 
-use napi::{bindgen_prelude::*, threadsafe_function::*};
-use std::sync::Arc;
+use napi::{JsObject, JsUnknown, Ref, bindgen_prelude::*, threadsafe_function::*};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 
 /// Client api of Nacos Config.
-#[napi]
+#[napi(custom_finalize)]
 pub struct NacosConfigClient {
-  inner: nacos_sdk::api::config::ConfigService,
+  inner: crate::ConfigBackend,
+  listeners: Arc<crate::ListenerRegistry<(String, String), NacosConfigChangeListener>>,
+  namespace: String,
+  /// None if the configs are served from the file serverAddr.
+  open_api: Option<Arc<crate::OpenApiClient>>,
+  resolver: ConfigResolver,
 }
 
 #[napi]
@@ -14,110 +21,436 @@ impl NacosConfigClient {
   /// Build a Config Client.
   #[napi(constructor)]
   pub fn new(
+    env: Env,
     client_options: crate::ClientOptions,
-    config_filter: Option<
-      ThreadsafeFunction<(
-        Option<crate::NacosConfigReq>,
-        Option<crate::NacosConfigResp>,
-      )>,
-    >,
+    #[napi(
+      ts_arg_type = "((err: Error | null, req?: NacosConfigReq | undefined | null, resp?: NacosConfigResp | undefined | null) => any) | NacosConfigFilterEntry | Array<NacosConfigFilterEntry> | undefined | null"
+    )]
+    config_filter: Option<Either3<JsFunction, Vec<JsObject>, JsObject>>,
   ) -> Result<NacosConfigClient> {
-    let props = nacos_sdk::api::props::ClientProps::new()
-      .server_addr(client_options.server_addr)
-      .namespace(client_options.namespace)
-      .app_name(
+    let is_file = client_options.server_addr.starts_with(crate::FILE_SCHEME);
+    let namespace = client_options.namespace.clone();
+    let check_beta = client_options.config_check_beta.unwrap_or(false);
+    // there is no open api of the file serverAddr
+    let open_api = (!is_file).then(|| {
+      Arc::new(crate::OpenApiClient::new(
+        &client_options.server_addr,
+        client_options.namespace.clone(),
         client_options
-          .app_name
-          .unwrap_or(nacos_sdk::api::constants::UNKNOWN.to_string()),
-      )
-      .config_load_cache_at_start(client_options.config_load_cache_at_start.unwrap_or(false));
-
-    // need enable_auth_plugin_http with username & password
-    let is_enable_auth_http =
-      client_options.username.is_some() && client_options.password.is_some();
-    // need enable_auth_plugin_aliyun with access_key & access_secret
-    let is_enable_auth_aliyun =
-      client_options.access_key.is_some() && client_options.access_secret.is_some();
-
-    let props = if is_enable_auth_http {
-      props
-        .auth_username(client_options.username.unwrap())
-        .auth_password(client_options.password.unwrap())
-    } else if is_enable_auth_aliyun {
-      props
-        .auth_access_key(client_options.access_key.unwrap())
-        .auth_access_secret(client_options.access_secret.unwrap())
-        .auth_signature_region_id(client_options.signature_region_id.unwrap())
-    } else {
-      props
-    };
+          .username
+          .clone()
+          .zip(client_options.password.clone()),
+      ))
+    });
+    let filter_failures = Arc::new(crate::FilterFailures::new(
+      client_options.config_filter_pass_through.unwrap_or(false),
+    ));
+    let mut filters: Vec<Box<dyn nacos_sdk::api::plugin::ConfigFilter>> = Vec::new();
+    let filter_timeout = client_options
+      .config_filter_timeout_ms
+      .map(|ms| std::time::Duration::from_millis(ms as u64))
+      .unwrap_or(crate::DEFAULT_FILTER_TIMEOUT);
 
-    let config_service_builder = if is_enable_auth_http {
-      nacos_sdk::api::config::ConfigServiceBuilder::new(props).enable_auth_plugin_http()
-    } else if is_enable_auth_aliyun {
-      nacos_sdk::api::config::ConfigServiceBuilder::new(props).enable_auth_plugin_aliyun()
-    } else {
-      nacos_sdk::api::config::ConfigServiceBuilder::new(props)
+    // The cipher filter first, so that configFilter sees the plaintext of response.
+    if let Some(options) = client_options.config_cipher {
+      filters.push(Box::new(crate::ConfigCipherFilter::new(
+        options,
+        filter_timeout,
+        filter_failures.clone(),
+      )?));
+    }
+
+    // Then the filters of js in order, each filters only the configs matched.
+    let config_filters = match config_filter {
+      None => Vec::new(),
+      Some(Either3::A(func)) => {
+        let mut entry = env.create_object()?;
+        entry.set_named_property("filter", func)?;
+        vec![entry]
+      }
+      Some(Either3::B(entries)) => entries,
+      Some(Either3::C(entry)) => vec![entry],
     };
+    for entry in config_filters {
+      filters.push(Box::new(crate::NacosConfigFilter::new(
+        &env,
+        entry,
+        filter_timeout,
+        filter_failures.clone(),
+      )?));
+    }
+
+    let filters = crate::ConfigFilterChain::new(
+      client_options.namespace.clone(),
+      filters,
+      filter_failures.clone(),
+    );
+    let snapshots = client_options
+      .config_snapshot_dir
+      .map(|dir| {
+        crate::ConfigSnapshots::new(
+          dir,
+          &client_options.namespace,
+          client_options.config_failover_mode,
+          filters.clone(),
+        )
+      })
+      .transpose()?
+      .map(Arc::new);
 
-    let config_service_builder = if let Some(filter) = config_filter {
-      config_service_builder.add_config_filter(Box::new(crate::NacosConfigFilter {
-        func: Arc::new(filter),
-      }))
+    let config_service = if is_file {
+      crate::ConfigBackend::File(crate::FileConfigStore::new(
+        &client_options.server_addr,
+        &client_options.namespace,
+        vec![Box::new(filters.clone())],
+      )?)
     } else {
-      config_service_builder
-    };
+      let props = nacos_sdk::api::props::ClientProps::new()
+        .server_addr(client_options.server_addr)
+        .namespace(client_options.namespace)
+        .app_name(
+          client_options
+            .app_name
+            .unwrap_or(nacos_sdk::api::constants::UNKNOWN.to_string()),
+        )
+        .config_load_cache_at_start(client_options.config_load_cache_at_start.unwrap_or(false));
 
-    let config_service = crate::get_runtime().block_on(async {
-      config_service_builder
-        .build()
-        .await
-        .map_err(|nacos_err| Error::from_reason(nacos_err.to_string()))
-    })?;
+      // need enable_auth_plugin_http with username & password
+      let is_enable_auth_http =
+        client_options.username.is_some() && client_options.password.is_some();
+      // need enable_auth_plugin_aliyun with access_key & access_secret
+      let is_enable_auth_aliyun =
+        client_options.access_key.is_some() && client_options.access_secret.is_some();
 
+      let props = if is_enable_auth_http {
+        props
+          .auth_username(client_options.username.unwrap())
+          .auth_password(client_options.password.unwrap())
+      } else if is_enable_auth_aliyun {
+        props
+          .auth_access_key(client_options.access_key.unwrap())
+          .auth_access_secret(client_options.access_secret.unwrap())
+          .auth_signature_region_id(client_options.signature_region_id.unwrap())
+      } else {
+        props
+      };
+
+      let config_service_builder = if is_enable_auth_http {
+        nacos_sdk::api::config::ConfigServiceBuilder::new(props).enable_auth_plugin_http()
+      } else if is_enable_auth_aliyun {
+        nacos_sdk::api::config::ConfigServiceBuilder::new(props).enable_auth_plugin_aliyun()
+      } else {
+        nacos_sdk::api::config::ConfigServiceBuilder::new(props)
+      };
+
+      let config_service = crate::get_runtime().block_on(async {
+        config_service_builder
+          .with_config_filters(vec![Box::new(filters.clone())])
+          .build()
+          .await
+          .map_err(|nacos_err| Error::from_reason(nacos_err.to_string()))
+      })?;
+      crate::ConfigBackend::Nacos(config_service)
+    };
+
+    let listeners = Arc::new(crate::ListenerRegistry::new());
+    let placeholders = client_options.config_placeholders.map(|options| {
+      Arc::new(crate::PlaceholderResolver::new(
+        config_service.clone(),
+        &listeners,
+        options,
+      ))
+    });
     Ok(NacosConfigClient {
       inner: config_service,
+      listeners,
+      resolver: ConfigResolver {
+        beta_marker: BetaMarker {
+          open_api: open_api.clone().filter(|_| check_beta),
+        },
+        placeholders,
+        schemas: Arc::new(crate::SchemaRegistry::new()),
+        filters,
+        filter_failures,
+        snapshots,
+      },
+      namespace,
+      open_api,
     })
   }
 
   /// Get config's content.
-  /// If it fails, pay attention to err
-  #[napi]
-  pub async fn get_config(&self, data_id: String, group: String) -> Result<String> {
-    let resp = self.get_config_resp(data_id, group).await?;
-    Ok(resp.content)
+  /// If it fails, pay attention to err, e.g. code 'ConfigNotFound' if the config does not exist
+  #[napi(ts_return_type = "Promise<string>")]
+  pub fn get_config(&self, env: Env, data_id: String, group: String) -> Result<JsObject> {
+    let (inner, resolver) = (self.inner.clone(), self.resolver.clone());
+    env.execute_tokio_future(
+      async move { Ok(fetch_config(&inner, &resolver, data_id, group).await) },
+      |env, ret| match ret {
+        Ok(conf_resp) => Ok(conf_resp.content),
+        Err(get_err) => Err(get_err.into_error(env)?),
+      },
+    )
+  }
+
+  /// Get config's content, or the default value if the config does not exist.
+  /// If it fails, pay attention to err, e.g. the server can not be connected
+  #[napi(ts_return_type = "Promise<string>")]
+  pub fn get_config_or_default(
+    &self,
+    env: Env,
+    data_id: String,
+    group: String,
+    default_value: String,
+  ) -> Result<JsObject> {
+    let (inner, resolver) = (self.inner.clone(), self.resolver.clone());
+    env.execute_tokio_future(
+      async move { Ok(fetch_config(&inner, &resolver, data_id, group).await) },
+      |env, ret| match ret {
+        Ok(conf_resp) => Ok(conf_resp.content),
+        Err(get_err) if get_err.is_not_found() => Ok(default_value),
+        Err(get_err) => Err(get_err.into_error(env)?),
+      },
+    )
   }
 
   /// Get NacosConfigResponse.
-  /// If it fails, pay attention to err
-  #[napi]
-  pub async fn get_config_resp(
+  /// If it fails, pay attention to err, e.g. code 'ConfigNotFound' if the config does not exist
+  #[napi(ts_return_type = "Promise<NacosConfigResponse>")]
+  pub fn get_config_resp(&self, env: Env, data_id: String, group: String) -> Result<JsObject> {
+    let (inner, resolver) = (self.inner.clone(), self.resolver.clone());
+    env.execute_tokio_future(
+      async move { Ok(fetch_config(&inner, &resolver, data_id, group).await) },
+      |env, ret| match ret {
+        Ok(conf_resp) => Ok(conf_resp),
+        Err(get_err) => Err(get_err.into_error(env)?),
+      },
+    )
+  }
+
+  /// Get many configs concurrently (at most 8 at a time), the results are keyed by dataId and then group,
+  /// e.g. `results[dataId][group]`.
+  /// A config failed to get has error instead of resp, e.g. code 'ConfigNotFound' if it does not exist.
+  #[napi(ts_return_type = "Promise<Record<string, Record<string, NacosConfigResult>>>")]
+  pub async fn get_configs(
     &self,
+    keys: Vec<NacosConfigKey>,
+  ) -> Result<HashMap<String, HashMap<String, NacosConfigResult>>> {
+    let permits = Arc::new(tokio::sync::Semaphore::new(GET_CONFIGS_CONCURRENCY));
+    let tasks: Vec<_> = keys
+      .into_iter()
+      .map(|key| {
+        let (inner, resolver) = (self.inner.clone(), self.resolver.clone());
+        let permits = permits.clone();
+        tokio::spawn(async move {
+          let _permit = permits.acquire_owned().await;
+          let resp = fetch_config(&inner, &resolver, key.data_id.clone(), key.group.clone())
+            .await
+            .map_err(GetConfigError::into_config_error);
+          (key, resp)
+        })
+      })
+      .collect();
+
+    let mut results: HashMap<String, HashMap<String, NacosConfigResult>> = HashMap::new();
+    for task in tasks {
+      let (key, resp) = task
+        .await
+        .map_err(|join_err| Error::from_reason(join_err.to_string()))?;
+      let (resp, error) = match resp {
+        Ok(conf_resp) => (Some(conf_resp), None),
+        Err(config_err) => (None, Some(config_err)),
+      };
+      results.entry(key.data_id.clone()).or_default().insert(
+        key.group.clone(),
+        NacosConfigResult {
+          data_id: key.data_id,
+          group: key.group,
+          resp,
+          error,
+        },
+      );
+    }
+    Ok(results)
+  }
+
+  /// Get config's content parsed by its format, one of json, yaml, properties or toml.
+  /// Format is the content's type, or the extension of dataId if the type is not structured.
+  /// If it fails, pay attention to err, e.g. code 'ConfigParseError' if the content can not be parsed
+  #[napi(ts_return_type = "Promise<any>")]
+  pub fn get_config_parsed(&self, env: Env, data_id: String, group: String) -> Result<JsObject> {
+    let (inner, resolver) = (self.inner.clone(), self.resolver.clone());
+    env.execute_tokio_future(
+      async move {
+        Ok(
+          fetch_config(&inner, &resolver, data_id, group)
+            .await
+            .map(|conf_resp| parse_config(&conf_resp)),
+        )
+      },
+      |env, ret| match ret {
+        Ok(Ok(value)) => env.to_js_value(&value),
+        Ok(Err(parse_err)) => Err(crate::throw_js_error(parse_err.into_js_error(env)?)),
+        Err(get_err) => Err(get_err.into_error(env)?),
+      },
+    )
+  }
+
+  /// Publish config, with options of type, tags, desc, appName and encryptedDataKey.
+  /// If it fails, pay attention to err, e.g. code 'ConfigSchemaInvalid' if the content does not match the schema
+  #[napi(ts_return_type = "Promise<boolean>")]
+  pub fn publish_config(
+    &self,
+    env: Env,
     data_id: String,
     group: String,
-  ) -> Result<NacosConfigResponse> {
-    let config_resp = self
-      .inner
-      .get_config(data_id, group)
-      .await
-      .map_err(|nacos_err| Error::from_reason(nacos_err.to_string()))?;
-    Ok(transfer_conf_resp(config_resp))
+    content: String,
+    options: Option<PublishConfigOptions>,
+  ) -> Result<JsObject> {
+    let (inner, resolver) = (self.inner.clone(), self.resolver.clone());
+    env.execute_tokio_future(
+      async move { Ok(publish_config(&inner, &resolver, data_id, group, content, options).await) },
+      |env, ret| match ret {
+        Ok(published) => Ok(published),
+        Err(publish_err) => Err(publish_err.into_error(env)?),
+      },
+    )
+  }
+
+  /// Publish config only if the md5 of config in server is the expected one, i.e. compare and swap.
+  /// If it fails, pay attention to err, e.g. code 'ConfigCasConflict' if the md5 of server has changed
+  #[napi(ts_return_type = "Promise<boolean>")]
+  pub fn publish_config_cas(
+    &self,
+    env: Env,
+    data_id: String,
+    group: String,
+    content: String,
+    expected_md5: String,
+  ) -> Result<JsObject> {
+    let (inner, resolver) = (self.inner.clone(), self.resolver.clone());
+    env.execute_tokio_future(
+      async move {
+        if let Err(publish_err) = check_schema(&resolver, &data_id, &group, None, &content).await {
+          return Ok(Err(publish_err));
+        }
+        let (published, filter_err) = resolver
+          .filters
+          .publish(
+            data_id.clone(),
+            group.clone(),
+            content,
+            None,
+            |data_id, group, content| {
+              inner.publish_config_cas(data_id, group, content, None, expected_md5)
+            },
+          )
+          .await;
+        Ok(PublishConfigError::check(
+          data_id, group, published, filter_err,
+        ))
+      },
+      |env, ret| match ret {
+        Ok(published) => Ok(published),
+        Err(publish_err) => Err(publish_err.into_error(env)?),
+      },
+    )
+  }
+
+  /// Publish beta (gray) config, only the clients of betaIps get it, others still get the stable one.
+  /// If it fails, pay attention to err, e.g. code 'ConfigSchemaInvalid' if the content does not match the schema
+  #[napi(ts_return_type = "Promise<boolean>")]
+  pub fn publish_beta_config(
+    &self,
+    env: Env,
+    data_id: String,
+    group: String,
+    content: String,
+    beta_ips: Vec<String>,
+  ) -> Result<JsObject> {
+    let (inner, resolver) = (self.inner.clone(), self.resolver.clone());
+    env.execute_tokio_future(
+      async move {
+        if let Err(publish_err) = check_schema(&resolver, &data_id, &group, None, &content).await {
+          return Ok(Err(publish_err));
+        }
+        let (published, filter_err) = resolver
+          .filters
+          .publish(
+            data_id.clone(),
+            group.clone(),
+            content,
+            None,
+            |data_id, group, content| {
+              inner.publish_config_beta(data_id, group, content, None, beta_ips.join(","))
+            },
+          )
+          .await;
+        Ok(PublishConfigError::check(
+          data_id, group, published, filter_err,
+        ))
+      },
+      |env, ret| match ret {
+        Ok(published) => Ok(published),
+        Err(publish_err) => Err(publish_err.into_error(env)?),
+      },
+    )
   }
 
-  /// Publish config.
+  /// Stop beta (gray) config, so that the clients of betaIps get the stable one again.
+  /// It is done by the http open api of nacos server.
   /// If it fails, pay attention to err
   #[napi]
-  pub async fn publish_config(
+  pub async fn stop_beta_config(&self, data_id: String, group: String) -> Result<bool> {
+    let Some(open_api) = self.open_api.as_ref() else {
+      return Err(Error::from_reason(
+        "beta config is not supported by file serverAddr",
+      ));
+    };
+    open_api
+      .stop_beta(&data_id, &group)
+      .await
+      .map_err(Error::from_reason)
+  }
+
+  /// Register the JSON Schema of config, which its content parsed by format (the same as `getConfigParsed`) must match.
+  /// The content does not match is rejected by publish, and held back from listeners (reported to `onInvalidConfig`),
+  /// the last valid config is kept as current. Register again to replace the schema.
+  /// If it fails, pay attention to err, e.g. the schema is not valid
+  #[napi]
+  pub fn register_schema(
     &self,
     data_id: String,
     group: String,
-    content: String,
-  ) -> Result<bool> {
+    #[napi(ts_arg_type = "object | boolean")] schema: serde_json::Value,
+  ) -> Result<()> {
     self
-      .inner
-      .publish_config(data_id, group, content, None)
-      .await
-      .map_err(|nacos_err| Error::from_reason(nacos_err.to_string()))
+      .resolver
+      .schemas
+      .register(data_id, group, &schema)
+      .map_err(Error::from_reason)
+  }
+
+  /// Unregister the JSON Schema of config, return false if not registered.
+  #[napi]
+  pub fn unregister_schema(&self, data_id: String, group: String) -> bool {
+    self.resolver.schemas.unregister(data_id, group)
+  }
+
+  /// Set the callback func, which is called once the config received does not match its schema.
+  /// Only one callback func is kept, set it again to replace, or null to remove.
+  #[napi]
+  pub fn on_invalid_config(
+    &self,
+    #[napi(ts_arg_type = "((err: Error | null, event: NacosInvalidConfigEvent) => any) | null")]
+    listener: Option<JsFunction>,
+  ) -> Result<()> {
+    let func = match listener {
+      Some(listener) => Some(listener.create_threadsafe_function(0, |ctx| Ok(vec![ctx.value]))?),
+      None => None,
+    };
+    self.resolver.schemas.set_on_invalid(func);
+    Ok(())
   }
 
   /// Remove config.
@@ -131,43 +464,517 @@ impl NacosConfigClient {
       .map_err(|nacos_err| Error::from_reason(nacos_err.to_string()))
   }
 
-  /// Add NacosConfigChangeListener callback func, which listen the config change.
+  /// Export the configs of namespace as a bundle, with the content as stored, e.g. encrypted for the configs of cipher.
+  /// The configs are listed by the http open api of nacos server, or the dir of file serverAddr.
   /// If it fails, pay attention to err
   #[napi]
-  pub async fn add_listener(
+  pub async fn export_configs(
     &self,
-    data_id: String,
-    group: String,
-    listener: ThreadsafeFunction<NacosConfigResponse>,
-  ) -> Result<()> {
-    self
-      .inner
-      .add_listener(
-        data_id,
-        group,
-        Arc::new(NacosConfigChangeListener {
-          func: Arc::new(listener),
-        }),
-      )
+    namespace: String,
+    options: Option<crate::ExportConfigsOptions>,
+  ) -> Result<crate::NacosConfigBundle> {
+    let configs = crate::list_configs(&self.inner, self.open_api.as_deref(), &namespace)
       .await
-      .map_err(|nacos_err| Error::from_reason(nacos_err.to_string()))?;
-    Ok(())
+      .map_err(Error::from_reason)?;
+    Ok(crate::NacosConfigBundle {
+      namespace,
+      configs: crate::filter_exported(configs, options)?,
+    })
   }
 
-  /// Remove NacosConfigChangeListener callback func, but noop....
-  /// The logic is not implemented internally, and only APIs are provided as compatibility.
-  /// Users maybe do not need it? Not removing the listener is not a big problem, Sorry!
+  /// Import the configs of bundle into the namespace of client, each is published as it is in the bundle,
+  /// e.g. encrypted, without config filters and the schema.
+  /// The config which does not exist is created, the one exists with the same md5 is unchanged,
+  /// and the one exists with other content is imported by options.conflict.
+  /// The config changed since it is planned is not published, with error code 'ConfigCasConflict'.
+  /// Return the report of what is done, or would be done by dry run, with the error of each config failed to publish.
+  /// If it fails, pay attention to err, e.g. the current configs can not be listed
   #[napi]
-  pub async fn remove_listener(
+  pub async fn import_configs(
     &self,
-    _data_id: String,
-    _group: String,
-    _listener: ThreadsafeFunction<NacosConfigResponse>,
-  ) -> Result<()> {
+    bundle: crate::NacosConfigBundle,
+    options: Option<crate::ImportConfigsOptions>,
+  ) -> Result<crate::NacosConfigImportReport> {
+    let conflict = crate::ImportConflict::new(
+      options
+        .as_ref()
+        .and_then(|options| options.conflict.as_deref()),
+    )?;
+    let dry_run = options.and_then(|options| options.dry_run).unwrap_or(false);
+    let current = crate::list_configs(&self.inner, self.open_api.as_deref(), &self.namespace)
+      .await
+      .map_err(Error::from_reason)?;
+    let mut report = crate::plan_import(&bundle.configs, &current, conflict, dry_run);
+    if dry_run || report.aborted {
+      return Ok(report);
+    }
+
+    let current_md5: HashMap<_, _> = current
+      .into_iter()
+      .map(|config| ((config.data_id, config.group), config.md5))
+      .collect();
+    for (item, config) in report.items.iter_mut().zip(bundle.configs) {
+      let current_md5 = match item.action {
+        crate::ConfigImportAction::Create => None,
+        crate::ConfigImportAction::Overwrite => current_md5
+          .get(&(config.data_id.clone(), config.group.clone()))
+          .cloned(),
+        _ => continue,
+      };
+      match import_config(&self.inner, &self.resolver.filters, config, current_md5).await {
+        Ok(action) => item.action = action,
+        Err(import_err) => item.error = Some(import_err),
+      }
+    }
+    Ok(report)
+  }
+
+  /// Add NacosConfigChangeListener callback func, which listen the config change.
+  /// Return a Subscription, dispose it to remove the listener.
+  /// If it fails, pay attention to err
+  #[napi(ts_return_type = "Promise<Subscription>")]
+  pub fn add_listener(
+    &self,
+    env: Env,
+    data_id: String,
+    group: String,
+    #[napi(ts_arg_type = "(err: Error | null, arg: NacosConfigResponse) => any")]
+    listener: JsFunction,
+  ) -> Result<JsObject> {
+    let js_func = env.create_reference(&listener)?;
+    let config_listener = NacosConfigChangeListener::new(
+      ConfigChangeSink::Callback(Arc::new(
+        listener.create_threadsafe_function(0, |ctx| Ok(vec![ctx.value]))?,
+      )),
+      self.resolver.clone(),
+    );
+
+    self.listen(env, data_id, group, js_func, config_listener)
+  }
+
+  /// Add NacosConfigChangeListener callback func, which listen the config change parsed by its format,
+  /// the same as `getConfigParsed`, err's code is 'ConfigParseError' if the content can not be parsed.
+  /// Return a Subscription, dispose it (or `removeListener` the func) to remove the listener.
+  /// If it fails, pay attention to err
+  #[napi(ts_return_type = "Promise<Subscription>")]
+  pub fn add_parsed_listener(
+    &self,
+    env: Env,
+    data_id: String,
+    group: String,
+    #[napi(ts_arg_type = "(err: Error | null, value: any, resp: NacosConfigResponse) => any")]
+    listener: JsFunction,
+  ) -> Result<JsObject> {
+    let js_func = env.create_reference(&listener)?;
+    let config_listener = NacosConfigChangeListener::new(
+      ConfigChangeSink::Parsed(Arc::new(listener.create_threadsafe_function(
+        0,
+        |ctx: ThreadSafeCallContext<ParsedConfig>| {
+          let (conf_resp, parsed) = ctx.value;
+          let (err, value) = match parsed {
+            Ok(value) => (
+              ctx.env.get_null()?.into_unknown(),
+              ctx.env.to_js_value(&value)?,
+            ),
+            Err(parse_err) => (
+              parse_err.into_js_error(&ctx.env)?.into_unknown(),
+              ctx.env.get_undefined()?.into_unknown(),
+            ),
+          };
+          let resp = unsafe {
+            JsUnknown::from_napi_value(
+              ctx.env.raw(),
+              NacosConfigResponse::to_napi_value(ctx.env.raw(), conf_resp)?,
+            )?
+          };
+          Ok(vec![err, value, resp])
+        },
+      )?)),
+      self.resolver.clone(),
+    );
+
+    self.listen(env, data_id, group, js_func, config_listener)
+  }
+
+  /// Add NacosConfigChangeListener callback func, which listen the config change with the previous one,
+  /// and the changed keys if content's type is properties, json or yaml.
+  /// Return a Subscription, dispose it (or `removeListener` the func) to remove the listener.
+  /// If it fails, pay attention to err
+  #[napi(ts_return_type = "Promise<Subscription>")]
+  pub fn add_change_listener(
+    &self,
+    env: Env,
+    data_id: String,
+    group: String,
+    #[napi(ts_arg_type = "(err: Error | null, arg: NacosConfigChangeEvent) => any")]
+    listener: JsFunction,
+  ) -> Result<JsObject> {
+    let js_func = env.create_reference(&listener)?;
+    let config_listener = NacosConfigChangeListener::new(
+      ConfigChangeSink::Change {
+        func: Arc::new(listener.create_threadsafe_function(0, |ctx| Ok(vec![ctx.value]))?),
+        previous: Mutex::new(None),
+      },
+      self.resolver.clone(),
+    );
+
+    self.listen(env, data_id, group, js_func, config_listener)
+  }
+
+  /// Remove NacosConfigChangeListener callback func, the same func which passed to `addListener`.
+  /// If the func was added more than once, only one of them is removed.
+  /// If it fails, pay attention to err
+  #[napi(ts_return_type = "Promise<void>")]
+  pub fn remove_listener(
+    &self,
+    env: Env,
+    data_id: String,
+    group: String,
+    #[napi(ts_arg_type = "(err: Error | null, arg: NacosConfigResponse) => any")]
+    listener: JsFunction,
+  ) -> Result<JsObject> {
+    let removed =
+      self
+        .listeners
+        .remove_by_func(&env, &(data_id.clone(), group.clone()), &listener)?;
+    if let Some(config_listener) = removed.as_ref() {
+      config_listener.active.store(false, Ordering::Relaxed);
+    }
+
+    let inner = self.inner.clone();
+    env.spawn_future(async move {
+      if let Some(config_listener) = removed {
+        inner
+          .remove_listener(data_id, group, config_listener)
+          .await
+          .map_err(|nacos_err| Error::from_reason(nacos_err.to_string()))?;
+      }
+      Ok(())
+    })
+  }
+
+  /// Watch the config change, return an async iterator, e.g. `for await (const resp of client.watch(dataId, group))`.
+  /// Changes are buffered until consumed, see WatchOptions. Break out of the loop to remove the listener.
+  /// If it fails, pay attention to err of the iterator
+  #[napi(ts_return_type = "NacosConfigWatcher")]
+  pub fn watch(
+    &self,
+    env: Env,
+    data_id: String,
+    group: String,
+    options: Option<crate::WatchOptions>,
+  ) -> Result<JsObject> {
+    let queue = Arc::new(crate::WatchQueue::new(options));
+    let config_listener = NacosConfigChangeListener::new(
+      ConfigChangeSink::Watch(queue.clone()),
+      self.resolver.clone(),
+    );
+
+    let key = (data_id.clone(), group.clone());
+    let id = self
+      .listeners
+      .insert(key.clone(), None, config_listener.clone());
+
+    let inner = self.inner.clone();
+    let listeners = self.listeners.clone();
+    let watch_queue = queue.clone();
+    let watch_key = key.clone();
+    spawn(async move {
+      if let Err(nacos_err) = inner.add_listener(data_id, group, config_listener).await {
+        if let Some(config_listener) = listeners.remove_unbound(&watch_key, id) {
+          config_listener.active.store(false, Ordering::Relaxed);
+        }
+        watch_queue.fail(nacos_err.to_string());
+      }
+    });
+
+    let watcher = NacosConfigWatcher {
+      queue,
+      target: ConfigSubscriptionTarget {
+        inner: self.inner.clone(),
+        listeners: self.listeners.clone(),
+        key,
+        id,
+      },
+    };
+    let mut object = watcher.into_instance(env)?.as_object(env);
+    crate::set_async_iterator(&env, &mut object)?;
+    Ok(object)
+  }
+
+  /// Compose the configs of layers into one, by deep merging their parsed contents (the same as `getConfigParsed`),
+  /// the later layer overrides the former, e.g. `[common.yaml, service.yaml, service-prod.yaml]`.
+  /// A layer which does not exist is seen as empty, and nacos-sdk does not notify its first publish.
+  /// The composed config keeps live by listening to every layer, the listener is called with the merged config
+  /// once any layer changes, or err if the layer can not be parsed.
+  /// Dispose the returned NacosComposedConfig to remove the listeners.
+  /// If it fails, pay attention to err, whose code is 'ConfigParseError' if a layer can not be parsed
+  #[napi(ts_return_type = "Promise<NacosComposedConfig>")]
+  pub fn compose(
+    &self,
+    env: Env,
+    layers: Vec<NacosConfigKey>,
+    #[napi(ts_arg_type = "(err: Error | null, event: NacosComposedChangeEvent) => any")]
+    listener: Option<JsFunction>,
+  ) -> Result<JsObject> {
+    let func = match listener {
+      Some(listener) => Some(crate::create_composed_listener(&listener)?),
+      None => None,
+    };
+    let composed = Arc::new(crate::ComposedLayers::new(layers.len(), func));
+
+    let mut layer_listeners = Vec::with_capacity(layers.len());
+    let mut targets = Vec::with_capacity(layers.len());
+    for (index, layer) in layers.into_iter().enumerate() {
+      let config_listener = NacosConfigChangeListener::new(
+        ConfigChangeSink::Compose {
+          composed: composed.clone(),
+          index,
+        },
+        self.resolver.clone(),
+      );
+      let key = (layer.data_id, layer.group);
+      let id = self
+        .listeners
+        .insert(key.clone(), None, config_listener.clone());
+      layer_listeners.push((key.clone(), config_listener));
+      targets.push(ConfigSubscriptionTarget {
+        inner: self.inner.clone(),
+        listeners: self.listeners.clone(),
+        key,
+        id,
+      });
+    }
+
+    let (inner, resolver) = (self.inner.clone(), self.resolver.clone());
+    let composed_layers = composed.clone();
+    env.execute_tokio_future(
+      async move {
+        // listen before fetch, so that no change is lost in between
+        let mut layer_keys = Vec::with_capacity(layer_listeners.len());
+        for ((data_id, group), config_listener) in layer_listeners {
+          layer_keys.push((data_id.clone(), group.clone()));
+          if let Err(nacos_err) = inner.add_listener(data_id, group, config_listener).await {
+            return Ok(Err(ComposeError::Listen(nacos_err)));
+          }
+        }
+
+        let fetches: Vec<_> = layer_keys
+          .into_iter()
+          .map(|(data_id, group)| {
+            let (inner, resolver) = (inner.clone(), resolver.clone());
+            tokio::spawn(async move { fetch_config(&inner, &resolver, data_id, group).await })
+          })
+          .collect();
+        for (index, fetch) in fetches.into_iter().enumerate() {
+          let fetched = fetch
+            .await
+            .map_err(|join_err| Error::from_reason(join_err.to_string()))?;
+          let value = match fetched {
+            Ok(conf_resp) => match parse_config(&conf_resp) {
+              Ok(value) => Some(value),
+              Err(parse_err) => return Ok(Err(ComposeError::Parse(parse_err))),
+            },
+            Err(get_err) if get_err.is_not_found() => None,
+            Err(get_err) => return Ok(Err(ComposeError::Get(get_err))),
+          };
+          composed_layers.set_fetched(index, value);
+        }
+        Ok(Ok(()))
+      },
+      move |env, ret| {
+        if let Err(compose_err) = ret {
+          for target in targets.iter() {
+            crate::SubscriptionTarget::dispose(target, env)?;
+          }
+          return Err(match compose_err {
+            ComposeError::Get(get_err) => get_err.into_error(env)?,
+            ComposeError::Parse(parse_err) => crate::throw_js_error(parse_err.into_js_error(env)?),
+            ComposeError::Listen(nacos_err) => Error::from_reason(nacos_err.to_string()),
+          });
+        }
+        let targets = targets
+          .into_iter()
+          .map(|target| Box::new(target) as Box<dyn crate::SubscriptionTarget>)
+          .collect();
+        crate::NacosComposedConfig::new(composed, targets).into_js_object(env)
+      },
+    )
+  }
+}
+
+impl ObjectFinalize for NacosConfigClient {
+  fn finalize(self, env: Env) -> Result<()> {
+    self.listeners.release_js_funcs(env)
+  }
+}
+
+impl NacosConfigClient {
+  /// Add the listener bind with js callback to nacos-sdk, resolve a Subscription.
+  fn listen(
+    &self,
+    env: Env,
+    data_id: String,
+    group: String,
+    js_func: Ref<()>,
+    config_listener: Arc<NacosConfigChangeListener>,
+  ) -> Result<JsObject> {
+    let key = (data_id.clone(), group.clone());
+    let id = self
+      .listeners
+      .insert(key.clone(), Some(js_func), config_listener.clone());
+
+    let target = ConfigSubscriptionTarget {
+      inner: self.inner.clone(),
+      listeners: self.listeners.clone(),
+      key,
+      id,
+    };
+    let (inner, resolver) = (self.inner.clone(), self.resolver.clone());
+    env.execute_tokio_future(
+      async move {
+        // The previous one of the first change is the current config, if any.
+        if let ConfigChangeSink::Change { previous, .. } = &*config_listener.sink
+          && let Ok(conf_resp) =
+            fetch_config(&inner, &resolver, data_id.clone(), group.clone()).await
+        {
+          *previous.lock().unwrap() = Some(conf_resp);
+        }
+        Ok(inner.add_listener(data_id, group, config_listener).await)
+      },
+      move |env, ret| match ret {
+        Ok(()) => crate::Subscription::new(target).into_js_object(env),
+        Err(nacos_err) => {
+          if let Some(config_listener) = target.listeners.remove_by_id(env, &target.key, id)? {
+            config_listener.active.store(false, Ordering::Relaxed);
+          }
+          Err(Error::from_reason(nacos_err.to_string()))
+        }
+      },
+    )
+  }
+}
+
+/// Async iterator of the config change, returned by `watch`.
+#[napi]
+pub struct NacosConfigWatcher {
+  queue: Arc<crate::WatchQueue<NacosConfigResponse>>,
+  target: ConfigSubscriptionTarget,
+}
+
+#[napi]
+impl NacosConfigWatcher {
+  /// Wait for the next config change, done after the watcher closed.
+  /// If it fails, pay attention to err
+  #[napi]
+  pub async fn next(&self) -> Result<NacosConfigWatchResult> {
+    let value = self.queue.recv().await?;
+    Ok(NacosConfigWatchResult {
+      done: value.is_none(),
+      value,
+    })
+  }
+
+  /// Close the watcher and remove its listener, `break` of `for await` calls it.
+  #[napi(js_name = "return")]
+  pub fn close(&self, env: Env) -> Result<NacosConfigWatchResult> {
+    self.queue.close();
+    crate::SubscriptionTarget::dispose(&self.target, &env)?;
+    Ok(NacosConfigWatchResult {
+      done: true,
+      value: None,
+    })
+  }
+}
+
+#[napi(object)]
+pub struct NacosConfigWatchResult {
+  /// Whether the watcher closed
+  pub done: bool,
+  /// The config change, absent if done
+  pub value: Option<NacosConfigResponse>,
+}
+
+/// Remove the listener of [`crate::Subscription`] from [`NacosConfigClient`].
+struct ConfigSubscriptionTarget {
+  inner: crate::ConfigBackend,
+  listeners: Arc<crate::ListenerRegistry<(String, String), NacosConfigChangeListener>>,
+  key: (String, String),
+  id: u64,
+}
+
+impl crate::SubscriptionTarget for ConfigSubscriptionTarget {
+  fn is_active(&self) -> bool {
+    self.listeners.contains_id(&self.key, self.id)
+  }
+
+  fn dispose(&self, env: &Env) -> Result<()> {
+    let Some(config_listener) = self.listeners.remove_by_id(env, &self.key, self.id)? else {
+      return Ok(());
+    };
+    // stop notify at once, and then remove it from nacos-sdk in background.
+    config_listener.active.store(false, Ordering::Relaxed);
+    let inner = self.inner.clone();
+    let (data_id, group) = self.key.clone();
+    spawn(async move {
+      let _ = inner.remove_listener(data_id, group, config_listener).await;
+    });
     Ok(())
   }
 }
 
+/// Max configs got at the same time by `getConfigs`.
+const GET_CONFIGS_CONCURRENCY: usize = 8;
+
+/// param config_tags of publish, comma separated.
+const KEY_PARAM_CONFIG_TAGS: &str = "config_tags";
+/// param desc of publish.
+const KEY_PARAM_DESC: &str = "desc";
+
+#[napi(object)]
+pub struct NacosConfigKey {
+  /// DataId
+  pub data_id: String,
+  /// Group
+  pub group: String,
+}
+
+#[napi(object)]
+pub struct NacosConfigResult {
+  /// DataId
+  pub data_id: String,
+  /// Group
+  pub group: String,
+  /// The config, absent if it failed to get
+  pub resp: Option<NacosConfigResponse>,
+  /// Why it failed to get, absent if got
+  pub error: Option<NacosConfigError>,
+}
+
+#[napi(object)]
+pub struct NacosConfigError {
+  /// Code of error, e.g. 'ConfigNotFound', all listed in the README, absent if it is not known
+  pub code: Option<String>,
+  /// Message of error
+  pub message: String,
+}
+
+#[napi(object)]
+pub struct PublishConfigOptions {
+  /// Content's Type; e.g. json,properties,xml,html,text,yaml
+  #[napi(js_name = "type")]
+  pub content_type: Option<String>,
+  /// Tags
+  pub tags: Option<Vec<String>>,
+  /// Description
+  pub desc: Option<String>,
+  /// AppName
+  pub app_name: Option<String>,
+  /// Content's Encrypted Data Key
+  pub encrypted_data_key: Option<String>,
+}
+
+#[derive(Clone)]
 #[napi(object)]
 pub struct NacosConfigResponse {
   /// Namespace/Tenant
@@ -182,22 +989,828 @@ pub struct NacosConfigResponse {
   pub content_type: String,
   /// Content's md5
   pub md5: String,
+  /// Whether the content is the beta (gray) one for this client, a hint checked by another request of the http open api,
+  /// which nacos server judges by the ip of http request, not always the one of grpc, e.g. behind a proxy.
+  /// Absent if `configCheckBeta` is not enabled, it can not be checked, or the config changed meanwhile
+  pub is_beta: Option<bool>,
+  /// Whether the content is read from the local snapshot in failover mode, so it may be stale
+  pub stale: bool,
 }
 
 pub struct NacosConfigChangeListener {
-  func: Arc<ThreadsafeFunction<NacosConfigResponse>>,
+  sink: Arc<ConfigChangeSink>,
+  active: Arc<AtomicBool>,
+  resolver: ConfigResolver,
+  /// The changes to resolve asynchronously one by one, None if they are resolved in `notify`.
+  resolving: Option<tokio::sync::mpsc::UnboundedSender<NacosConfigResponse>>,
+}
+
+impl NacosConfigChangeListener {
+  fn new(sink: ConfigChangeSink, resolver: ConfigResolver) -> Arc<Self> {
+    Arc::new_cyclic(|this: &Weak<Self>| {
+      // the changes are delivered in the order notified, even if some take longer to resolve.
+      let resolving = resolver.is_async().then(|| {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let this = this.clone();
+        spawn(async move {
+          while let Some(conf_resp) = receiver.recv().await {
+            let Some(config_listener) = this.upgrade() else {
+              return;
+            };
+            if let Some(conf_resp) = config_listener.resolver.resolve_notified(conf_resp).await {
+              config_listener.deliver(conf_resp);
+            }
+          }
+        });
+        sender
+      });
+      NacosConfigChangeListener {
+        sink: Arc::new(sink),
+        active: Arc::new(AtomicBool::new(true)),
+        resolver,
+        resolving,
+      }
+    })
+  }
+
+  /// Send the change resolved to the sink, unless it does not match the schema or the listener removed.
+  fn deliver(&self, conf_resp: NacosConfigResponse) {
+    // held back if it does not match the schema, reported to `onInvalidConfig` instead.
+    let Ok(conf_resp) = self.resolver.schemas.check(conf_resp) else {
+      return;
+    };
+    // it may be removed while resolving
+    if self.active.load(Ordering::Relaxed) {
+      if let Some(placeholders) = self.resolver.placeholders.as_ref() {
+        placeholders.notified(&conf_resp.data_id, &conf_resp.group, &conf_resp.content);
+      }
+      self.sink.send(conf_resp);
+    }
+  }
+}
+
+/// Where the config change goes.
+enum ConfigChangeSink {
+  /// Call the js callback func.
+  Callback(Arc<ThreadsafeFunction<NacosConfigResponse>>),
+  /// Push to the queue of a [`NacosConfigWatcher`].
+  Watch(Arc<crate::WatchQueue<NacosConfigResponse>>),
+  /// Call the js callback func with the parsed config.
+  Parsed(Arc<ThreadsafeFunction<ParsedConfig, ErrorStrategy::Fatal>>),
+  /// Call the js callback func with the previous config of this listener.
+  Change {
+    func: Arc<ThreadsafeFunction<NacosConfigChangeEvent>>,
+    previous: Mutex<Option<NacosConfigResponse>>,
+  },
+  /// Update the layer of a [`crate::NacosComposedConfig`].
+  Compose {
+    composed: Arc<crate::ComposedLayers>,
+    index: usize,
+  },
+}
+
+impl ConfigChangeSink {
+  fn send(&self, conf_resp: NacosConfigResponse) {
+    match self {
+      ConfigChangeSink::Callback(func) => {
+        func.call(Ok(conf_resp), ThreadsafeFunctionCallMode::NonBlocking);
+      }
+      ConfigChangeSink::Watch(queue) => queue.push(conf_resp),
+      ConfigChangeSink::Parsed(func) => {
+        let parsed = parse_config(&conf_resp);
+        func.call((conf_resp, parsed), ThreadsafeFunctionCallMode::NonBlocking);
+      }
+      ConfigChangeSink::Change { func, previous } => {
+        let previous = previous.lock().unwrap().replace(conf_resp.clone());
+        let changes = diff_config(previous.as_ref(), &conf_resp);
+        let event = NacosConfigChangeEvent {
+          previous,
+          current: conf_resp,
+          changes,
+        };
+        func.call(Ok(event), ThreadsafeFunctionCallMode::NonBlocking);
+      }
+      ConfigChangeSink::Compose { composed, index } => composed.update(*index, conf_resp),
+    }
+  }
 }
 
 impl nacos_sdk::api::config::ConfigChangeListener for NacosConfigChangeListener {
   fn notify(&self, config_resp: nacos_sdk::api::config::ConfigResponse) {
-    let listen = self.func.clone();
+    if !self.active.load(Ordering::Relaxed) {
+      return;
+    }
+    // skipped as the config filters fail closed
+    if self.resolver.filter_failures.is_skipped(
+      config_resp.data_id(),
+      config_resp.group(),
+      config_resp.md5(),
+    ) {
+      return;
+    }
+    if let Some(snapshots) = self.resolver.snapshots.as_ref() {
+      // written in background, in the order of notify
+      if config_resp.content().is_empty() {
+        snapshots.remove(config_resp.data_id(), config_resp.group());
+      } else {
+        snapshots.save(&config_resp);
+      }
+    }
 
     let conf_resp = transfer_conf_resp(config_resp);
+    match self.resolving.as_ref() {
+      Some(resolving) => {
+        let _ = resolving.send(conf_resp);
+      }
+      None => self.deliver(conf_resp),
+    }
+  }
+}
 
-    std::thread::spawn(move || {
-      listen.call(Ok(conf_resp), ThreadsafeFunctionCallMode::NonBlocking);
-    });
+/// Resolve the config of nacos-sdk before it reaches js, mark it beta, expand its placeholders and check its schema.
+#[derive(Clone)]
+struct ConfigResolver {
+  beta_marker: BetaMarker,
+  placeholders: Option<Arc<crate::PlaceholderResolver>>,
+  schemas: Arc<crate::SchemaRegistry>,
+  filters: crate::ConfigFilterChain,
+  filter_failures: Arc<crate::FilterFailures>,
+  snapshots: Option<Arc<crate::ConfigSnapshots>>,
+}
+
+impl ConfigResolver {
+  /// Whether the config notified needs to be resolved asynchronously, i.e. checked beta or expanded.
+  fn is_async(&self) -> bool {
+    self.beta_marker.open_api.is_some() || self.placeholders.is_some()
+  }
+
+  /// Resolve the config notified, before it is delivered to the listener.
+  /// None if its placeholders can not be expanded, e.g. in cycle, which is skipped as `getConfig` rejects it.
+  async fn resolve_notified(&self, conf_resp: NacosConfigResponse) -> Option<NacosConfigResponse> {
+    let conf_resp = self.beta_marker.mark(conf_resp).await;
+    self.expand(&conf_resp).await.ok()
+  }
+
+  /// Resolve the config got, the last valid one is returned if it does not match the schema.
+  /// The stale one is read from the snapshot, which is not marked beta as the server can not be reached.
+  async fn resolve(
+    &self,
+    config_resp: nacos_sdk::api::config::ConfigResponse,
+    stale: bool,
+  ) -> std::result::Result<NacosConfigResponse, GetConfigCause> {
+    let conf_resp = if stale {
+      NacosConfigResponse {
+        stale: true,
+        ..transfer_conf_resp(config_resp)
+      }
+    } else {
+      self.beta_marker.mark(transfer_conf_resp(config_resp)).await
+    };
+    let conf_resp = self
+      .expand(&conf_resp)
+      .await
+      .map_err(GetConfigCause::Placeholder)?;
+    match self.schemas.check(conf_resp) {
+      Ok(conf_resp) => Ok(conf_resp),
+      Err(rejected) => rejected
+        .last_valid
+        .map(|last_valid| *last_valid)
+        .ok_or(GetConfigCause::SchemaInvalid(rejected.errors)),
+    }
+  }
+
+  /// Expand the placeholders of content if enabled.
+  async fn expand(
+    &self,
+    conf_resp: &NacosConfigResponse,
+  ) -> std::result::Result<NacosConfigResponse, crate::PlaceholderError> {
+    let mut conf_resp = conf_resp.clone();
+    if let Some(placeholders) = self.placeholders.as_ref() {
+      conf_resp.content = placeholders
+        .expand(&conf_resp.data_id, &conf_resp.group, &conf_resp.content)
+        .await?;
+    }
+    Ok(conf_resp)
+  }
+}
+
+/// Tell whether the config is the beta one for this client, by the flag of nacos server queried from the open api.
+/// nacos-sdk neither tells it of the config queried nor pushed, so it is only checked if `configCheckBeta` is enabled,
+/// by the ip of another http request, which may not be the one of grpc connection, e.g. behind a proxy.
+#[derive(Clone)]
+struct BetaMarker {
+  /// None if it is not enabled, or the configs are served from the file serverAddr, which has no beta.
+  open_api: Option<Arc<crate::OpenApiClient>>,
+}
+
+impl BetaMarker {
+  /// Set `is_beta` of the config, kept absent if it can not be checked.
+  async fn mark(&self, mut conf_resp: NacosConfigResponse) -> NacosConfigResponse {
+    if let Some(open_api) = self.open_api.as_ref() {
+      conf_resp.is_beta = open_api
+        .query_is_beta(&conf_resp.data_id, &conf_resp.group, &conf_resp.md5)
+        .await
+        .ok();
+    }
+    conf_resp
+  }
+}
+
+/// Get config from nacos-sdk and resolve it, keep dataId and group for the error.
+async fn fetch_config(
+  inner: &crate::ConfigBackend,
+  resolver: &ConfigResolver,
+  data_id: String,
+  group: String,
+) -> std::result::Result<NacosConfigResponse, GetConfigError> {
+  let get_err = |cause| GetConfigError {
+    data_id: data_id.clone(),
+    group: group.clone(),
+    cause,
+  };
+  let snapshots = resolver.snapshots.as_ref();
+  let failover_mode = snapshots.map(|snapshots| snapshots.mode());
+  if failover_mode == Some(crate::FailoverMode::Always)
+    && let Some(snapshots) = snapshots
+    && let Some(config_resp) = snapshots.load(&data_id, &group).await
+  {
+    // served next time
+    spawn(refresh_snapshot(
+      inner.clone(),
+      snapshots.clone(),
+      data_id.clone(),
+      group.clone(),
+    ));
+    return resolver.resolve(config_resp, true).await.map_err(get_err);
+  }
+
+  let get = crate::catch_filter_error(inner.get_config(data_id.clone(), group.clone()));
+  let (config_resp, filter_err) = if failover_mode == Some(crate::FailoverMode::OnError) {
+    // nacos-sdk keeps waiting while the server can not be reached
+    match tokio::time::timeout(crate::FAILOVER_READ_TIMEOUT, get).await {
+      Ok(got) => got,
+      Err(_) => (
+        Err(nacos_sdk::api::error::Error::ClientUnhealthy(
+          "get config timed out".to_string(),
+        )),
+        None,
+      ),
+    }
+  } else {
+    get.await
+  };
+  if let Some(filter_err) = filter_err {
+    return Err(get_err(GetConfigCause::Filter(filter_err)));
+  }
+  match config_resp {
+    Ok(config_resp) => {
+      if let Some(snapshots) = snapshots {
+        snapshots.save(&config_resp).wait().await;
+      }
+      resolver.resolve(config_resp, false).await.map_err(get_err)
+    }
+    Err(nacos_err) => {
+      let get_err = get_err(GetConfigCause::Nacos(nacos_err));
+      if let Some(snapshots) = snapshots {
+        if get_err.is_not_found() {
+          snapshots.remove(&data_id, &group).wait().await;
+        } else if failover_mode == Some(crate::FailoverMode::OnError)
+          && let Some(config_resp) = snapshots.load(&data_id, &group).await
+        {
+          return resolver
+            .resolve(config_resp, true)
+            .await
+            .map_err(|cause| GetConfigError { cause, ..get_err });
+        }
+      }
+      Err(get_err)
+    }
+  }
+}
+
+/// Publish the config of bundle as it is, only if the config is still the one planned, i.e. it does not exist,
+/// or its md5 is `current_md5`. Return the action done, Unchanged if the same config is created since.
+async fn import_config(
+  inner: &crate::ConfigBackend,
+  filters: &crate::ConfigFilterChain,
+  config: crate::NacosConfigBundleItem,
+  current_md5: Option<String>,
+) -> std::result::Result<crate::ConfigImportAction, NacosConfigError> {
+  let (data_id, group) = (config.data_id.clone(), config.group.clone());
+  let (action, published) = match current_md5 {
+    Some(current_md5) => (
+      crate::ConfigImportAction::Overwrite,
+      filters
+        .publish_unfiltered(
+          config.data_id,
+          config.group,
+          config.content,
+          config.encrypted_data_key,
+          |data_id, group, content| {
+            inner.publish_config_cas(
+              data_id,
+              group,
+              content,
+              Some(config.content_type),
+              current_md5,
+            )
+          },
+        )
+        .await,
+    ),
+    None => {
+      // nacos can not create only if absent, so check it right before
+      let (got, _) =
+        crate::catch_filter_error(inner.get_config(data_id.clone(), group.clone())).await;
+      match got {
+        Err(nacos_sdk::api::error::Error::ConfigNotFound(_)) => {}
+        Ok(got) if *got.md5() == config.md5 => return Ok(crate::ConfigImportAction::Unchanged),
+        Ok(_) => {
+          return Err(NacosConfigError {
+            code: Some("ConfigCasConflict".to_string()),
+            message: format!("config is created since planned, dataId={data_id}, group={group}"),
+          });
+        }
+        Err(nacos_err) => {
+          return Err(NacosConfigError {
+            code: None,
+            message: nacos_err.to_string(),
+          });
+        }
+      }
+      (
+        crate::ConfigImportAction::Create,
+        filters
+          .publish_unfiltered(
+            config.data_id,
+            config.group,
+            config.content,
+            config.encrypted_data_key,
+            |data_id, group, content| {
+              inner.publish_config(data_id, group, content, Some(config.content_type))
+            },
+          )
+          .await,
+      )
+    }
+  };
+  match PublishConfigError::check(data_id, group, published, None) {
+    Ok(true) => Ok(action),
+    Ok(false) => Err(NacosConfigError {
+      code: None,
+      message: "publish config failed".to_string(),
+    }),
+    Err(publish_err) => Err(publish_err.into_config_error()),
+  }
+}
+
+/// Refresh the snapshot served in failover mode 'always' by the server, which is not reached by the get.
+async fn refresh_snapshot(
+  inner: crate::ConfigBackend,
+  snapshots: Arc<crate::ConfigSnapshots>,
+  data_id: String,
+  group: String,
+) {
+  let get = crate::catch_filter_error(inner.get_config(data_id.clone(), group.clone()));
+  match tokio::time::timeout(crate::FAILOVER_READ_TIMEOUT, get).await {
+    Ok((Ok(config_resp), None)) => snapshots.save(&config_resp).wait().await,
+    Ok((Err(nacos_sdk::api::error::Error::ConfigNotFound(_)), _)) => {
+      snapshots.remove(&data_id, &group).wait().await
+    }
+    _ => {}
+  }
+}
+
+/// Publish config with options if any, after checked by its schema.
+async fn publish_config(
+  inner: &crate::ConfigBackend,
+  resolver: &ConfigResolver,
+  data_id: String,
+  group: String,
+  content: String,
+  options: Option<PublishConfigOptions>,
+) -> std::result::Result<bool, PublishConfigError> {
+  let content_type = options.as_ref().and_then(|o| o.content_type.as_deref());
+  check_schema(resolver, &data_id, &group, content_type, &content).await?;
+  let filters = &resolver.filters;
+  let (published, filter_err) = match options {
+    None => {
+      filters
+        .publish(
+          data_id.clone(),
+          group.clone(),
+          content,
+          None,
+          |data_id, group, content| inner.publish_config(data_id, group, content, None),
+        )
+        .await
+    }
+    Some(options) => {
+      let mut params = HashMap::new();
+      if let Some(tags) = options.tags {
+        params.insert(KEY_PARAM_CONFIG_TAGS.to_string(), tags.join(","));
+      }
+      if let Some(desc) = options.desc {
+        params.insert(KEY_PARAM_DESC.to_string(), desc);
+      }
+      if let Some(app_name) = options.app_name {
+        params.insert(
+          nacos_sdk::api::config::constants::KEY_PARAM_APP_NAME.to_string(),
+          app_name,
+        );
+      }
+      filters
+        .publish(
+          data_id.clone(),
+          group.clone(),
+          content,
+          options.encrypted_data_key,
+          |data_id, group, content| {
+            inner.publish_config_param(data_id, group, content, options.content_type, None, params)
+          },
+        )
+        .await
+    }
+  };
+  PublishConfigError::check(data_id, group, published, filter_err)
+}
+
+/// Why the layers can not be composed.
+enum ComposeError {
+  Get(GetConfigError),
+  Parse(ConfigParseError),
+  Listen(nacos_sdk::api::error::Error),
+}
+
+/// The config can not be got, as js Error with code 'ConfigNotFound' if it does not exist,
+/// 'ConfigPlaceholderCycle' if its placeholders refer to themselves,
+/// 'ConfigPlaceholderLimit' if they are nested too deep or expanded too long,
+/// 'ConfigSchemaInvalid' if it does not match the schema and there is no valid one before,
+/// or 'ConfigFilterError' if config filters fail.
+struct GetConfigError {
+  data_id: String,
+  group: String,
+  cause: GetConfigCause,
+}
+
+enum GetConfigCause {
+  Nacos(nacos_sdk::api::error::Error),
+  Placeholder(crate::PlaceholderError),
+  SchemaInvalid(Vec<String>),
+  Filter(String),
+}
+
+impl GetConfigError {
+  fn is_not_found(&self) -> bool {
+    matches!(
+      self.cause,
+      GetConfigCause::Nacos(nacos_sdk::api::error::Error::ConfigNotFound(_))
+    )
+  }
+
+  fn code(&self) -> Option<&'static str> {
+    match &self.cause {
+      _ if self.is_not_found() => Some("ConfigNotFound"),
+      GetConfigCause::Placeholder(placeholder_err) => Some(placeholder_err.code()),
+      GetConfigCause::SchemaInvalid(_) => Some("ConfigSchemaInvalid"),
+      GetConfigCause::Filter(_) => Some("ConfigFilterError"),
+      GetConfigCause::Nacos(_) => None,
+    }
+  }
+
+  fn message(&self) -> String {
+    match &self.cause {
+      _ if self.is_not_found() => format!(
+        "config not found, dataId={}, group={}: {}",
+        self.data_id,
+        self.group,
+        self.cause_message()
+      ),
+      GetConfigCause::Placeholder(crate::PlaceholderError::Cycle { .. }) => format!(
+        "config placeholder cycle, dataId={}, group={}: {}",
+        self.data_id,
+        self.group,
+        self.cause_message()
+      ),
+      GetConfigCause::Placeholder(_) => format!(
+        "config placeholder limit exceeded, dataId={}, group={}: {}",
+        self.data_id,
+        self.group,
+        self.cause_message()
+      ),
+      GetConfigCause::SchemaInvalid(_) => format!(
+        "config does not match the schema, dataId={}, group={}: {}",
+        self.data_id,
+        self.group,
+        self.cause_message()
+      ),
+      GetConfigCause::Filter(_) => format!(
+        "config filter failed, dataId={}, group={}: {}",
+        self.data_id,
+        self.group,
+        self.cause_message()
+      ),
+      GetConfigCause::Nacos(_) => self.cause_message(),
+    }
+  }
+
+  fn cause_message(&self) -> String {
+    match &self.cause {
+      GetConfigCause::Nacos(nacos_err) => nacos_err.to_string(),
+      GetConfigCause::Placeholder(placeholder_err) => placeholder_err.to_string(),
+      GetConfigCause::SchemaInvalid(errors) => errors.join("; "),
+      GetConfigCause::Filter(filter_err) => filter_err.clone(),
+    }
+  }
+
+  fn into_error(self, env: &Env) -> Result<Error> {
+    if let GetConfigCause::SchemaInvalid(errors) = &self.cause {
+      let error = crate::create_schema_error(env, &self.data_id, &self.group, errors)?;
+      return Ok(crate::throw_js_error(error));
+    }
+    let Some(code) = self.code() else {
+      return Ok(Error::from_reason(self.message()));
+    };
+    let mut error = crate::create_coded_error(env, code, self.message())?;
+    error.set_named_property("dataId", env.create_string(&self.data_id)?)?;
+    error.set_named_property("group", env.create_string(&self.group)?)?;
+    Ok(crate::throw_js_error(error))
+  }
+
+  fn into_config_error(self) -> NacosConfigError {
+    NacosConfigError {
+      code: self.code().map(str::to_string),
+      message: self.message(),
+    }
+  }
+}
+
+/// Check the content to publish by its schema if any.
+/// The same as the config received, it is checked with its placeholders expanded, or as it is if they can not be expanded.
+async fn check_schema(
+  resolver: &ConfigResolver,
+  data_id: &str,
+  group: &str,
+  content_type: Option<&str>,
+  content: &str,
+) -> std::result::Result<(), PublishConfigError> {
+  if !resolver.schemas.is_registered(data_id, group) {
+    return Ok(());
+  }
+  let expanded = match resolver.placeholders.as_ref() {
+    Some(placeholders) => placeholders.expand(data_id, group, content).await.ok(),
+    None => None,
+  };
+  resolver
+    .schemas
+    .validate(
+      data_id,
+      group,
+      content_type,
+      expanded.as_deref().unwrap_or(content),
+    )
+    .map_err(|errors| PublishConfigError {
+      data_id: data_id.to_string(),
+      group: group.to_string(),
+      cause: PublishConfigCause::SchemaInvalid(errors),
+    })
+}
+
+/// The config can not be published, as js Error with code 'ConfigCasConflict' if the md5 of server has changed,
+/// 'ConfigSchemaInvalid' if the content does not match the schema, or 'ConfigFilterError' if config filters fail.
+struct PublishConfigError {
+  data_id: String,
+  group: String,
+  cause: PublishConfigCause,
+}
+
+enum PublishConfigCause {
+  Nacos(nacos_sdk::api::error::Error),
+  SchemaInvalid(Vec<String>),
+  Filter(String),
+}
+
+impl PublishConfigError {
+  /// Check the result of publish, the error of config filters is prior to the one of nacos.
+  fn check(
+    data_id: String,
+    group: String,
+    published: nacos_sdk::api::error::Result<bool>,
+    filter_err: Option<String>,
+  ) -> std::result::Result<bool, Self> {
+    let cause = match (published, filter_err) {
+      (_, Some(filter_err)) => PublishConfigCause::Filter(filter_err),
+      (Ok(published), None) => return Ok(published),
+      (Err(nacos_err), None) => PublishConfigCause::Nacos(nacos_err),
+    };
+    Err(PublishConfigError {
+      data_id,
+      group,
+      cause,
+    })
+  }
+
+  fn into_config_error(self) -> NacosConfigError {
+    let (data_id, group) = (self.data_id, self.group);
+    let (code, message) = match self.cause {
+      PublishConfigCause::SchemaInvalid(errors) => (
+        Some("ConfigSchemaInvalid"),
+        format!(
+          "config does not match the schema, dataId={data_id}, group={group}: {}",
+          errors.join("; ")
+        ),
+      ),
+      PublishConfigCause::Nacos(nacos_err) if is_cas_conflict(&nacos_err) => (
+        Some("ConfigCasConflict"),
+        format!("publish config cas conflict, dataId={data_id}, group={group}: {nacos_err}"),
+      ),
+      PublishConfigCause::Filter(filter_err) => (
+        Some("ConfigFilterError"),
+        format!("config filter failed, dataId={data_id}, group={group}: {filter_err}"),
+      ),
+      PublishConfigCause::Nacos(nacos_err) => (None, nacos_err.to_string()),
+    };
+    NacosConfigError {
+      code: code.map(str::to_string),
+      message,
+    }
+  }
+
+  fn into_error(self, env: &Env) -> Result<Error> {
+    let (data_id, group) = (self.data_id, self.group);
+    match self.cause {
+      PublishConfigCause::SchemaInvalid(errors) => Ok(crate::throw_js_error(
+        crate::create_schema_error(env, &data_id, &group, &errors)?,
+      )),
+      PublishConfigCause::Nacos(nacos_err) if is_cas_conflict(&nacos_err) => {
+        let mut error = crate::create_coded_error(
+          env,
+          "ConfigCasConflict",
+          format!("publish config cas conflict, dataId={data_id}, group={group}: {nacos_err}"),
+        )?;
+        error.set_named_property("dataId", env.create_string(&data_id)?)?;
+        error.set_named_property("group", env.create_string(&group)?)?;
+        Ok(crate::throw_js_error(error))
+      }
+      PublishConfigCause::Filter(filter_err) => {
+        let mut error = crate::create_coded_error(
+          env,
+          "ConfigFilterError",
+          format!("config filter failed, dataId={data_id}, group={group}: {filter_err}"),
+        )?;
+        error.set_named_property("dataId", env.create_string(&data_id)?)?;
+        error.set_named_property("group", env.create_string(&group)?)?;
+        Ok(crate::throw_js_error(error))
+      }
+      PublishConfigCause::Nacos(nacos_err) => Ok(Error::from_reason(nacos_err.to_string())),
+    }
+  }
+}
+
+/// Nacos server rejects the cas publish with "Cas publish fail, server md5 may have changed."
+fn is_cas_conflict(nacos_err: &nacos_sdk::api::error::Error) -> bool {
+  match nacos_err {
+    nacos_sdk::api::error::Error::ErrResult(msg) => {
+      msg.to_ascii_lowercase().contains("cas publish fail")
+    }
+    _ => false,
+  }
+}
+
+/// The config content can not be parsed, as js Error with code 'ConfigParseError'.
+pub(crate) struct ConfigParseError {
+  data_id: String,
+  group: String,
+  format: String,
+  message: String,
+  line: Option<u32>,
+  column: Option<u32>,
+}
+
+impl ConfigParseError {
+  pub(crate) fn into_js_error(self, env: &Env) -> Result<JsObject> {
+    let mut position = String::new();
+    if let (Some(line), Some(column)) = (self.line, self.column) {
+      position = format!(", line={line}, column={column}");
+    }
+    let mut error = crate::create_coded_error(
+      env,
+      "ConfigParseError",
+      format!(
+        "parse config failed, dataId={}, group={}, format={}{}: {}",
+        self.data_id, self.group, self.format, position, self.message
+      ),
+    )?;
+    error.set_named_property("dataId", env.create_string(&self.data_id)?)?;
+    error.set_named_property("group", env.create_string(&self.group)?)?;
+    error.set_named_property("format", env.create_string(&self.format)?)?;
+    if let Some(line) = self.line {
+      error.set_named_property("line", env.create_uint32(line)?)?;
+    }
+    if let Some(column) = self.column {
+      error.set_named_property("column", env.create_uint32(column)?)?;
+    }
+    Ok(error)
+  }
+}
+
+/// The config with its parsed value.
+pub(crate) type ParsedConfig = (
+  NacosConfigResponse,
+  std::result::Result<serde_json::Value, ConfigParseError>,
+);
+
+/// Parse the config by its format, see [`crate::config_format`].
+pub(crate) fn parse_config(
+  conf_resp: &NacosConfigResponse,
+) -> std::result::Result<serde_json::Value, ConfigParseError> {
+  let format = crate::config_format(&conf_resp.content_type, &conf_resp.data_id);
+  let parse_err = |message: String, line: Option<u32>, column: Option<u32>| ConfigParseError {
+    data_id: conf_resp.data_id.clone(),
+    group: conf_resp.group.clone(),
+    format: format.clone(),
+    message,
+    line,
+    column,
+  };
+  match crate::parse_structured(&format, &conf_resp.content) {
+    Some(Ok(value)) => Ok(value),
+    Some(Err(err)) => Err(parse_err(err.message, err.line, err.column)),
+    None => Err(parse_err("unsupported format".to_string(), None, None)),
+  }
+}
+
+#[napi(object)]
+pub struct NacosConfigChangeEvent {
+  /// The previous config, absent if it not existed
+  pub previous: Option<NacosConfigResponse>,
+  /// The current config
+  pub current: NacosConfigResponse,
+  /// Changed keys, only if content's type (or dataId's extension) is properties, json, yaml or toml, and both can be parsed
+  pub changes: Option<Vec<NacosConfigItemChange>>,
+}
+
+#[napi(string_enum = "lowercase")]
+pub enum ConfigChangeType {
+  Added,
+  Removed,
+  Modified,
+}
+
+#[napi(object)]
+pub struct NacosConfigItemChange {
+  /// Key, nested key of json and yaml is flatten like `a.b[0].c`
+  pub key: String,
+  /// Type of change, one of 'added', 'removed', 'modified'
+  pub change_type: ConfigChangeType,
+  /// Value before, absent if added
+  pub previous: Option<String>,
+  /// Value after, absent if removed
+  pub current: Option<String>,
+}
+
+/// Diff keys of two configs, the config not existed is seen as empty.
+/// Format is the content's type, or the extension of dataId if the type is not structured.
+fn diff_config(
+  previous: Option<&NacosConfigResponse>,
+  current: &NacosConfigResponse,
+) -> Option<Vec<NacosConfigItemChange>> {
+  let format = crate::config_format(&current.content_type, &current.data_id);
+  let current_flat = crate::flatten(&crate::parse_structured(&format, &current.content)?.ok()?);
+  let previous_flat = match previous {
+    Some(previous) => crate::flatten(&crate::parse_structured(&format, &previous.content)?.ok()?),
+    None => Default::default(),
+  };
+
+  let mut changes = Vec::new();
+  for (key, value) in current_flat.iter() {
+    match previous_flat.get(key) {
+      None => changes.push(NacosConfigItemChange {
+        key: key.clone(),
+        change_type: ConfigChangeType::Added,
+        previous: None,
+        current: Some(value.clone()),
+      }),
+      Some(old) if old != value => changes.push(NacosConfigItemChange {
+        key: key.clone(),
+        change_type: ConfigChangeType::Modified,
+        previous: Some(old.clone()),
+        current: Some(value.clone()),
+      }),
+      Some(_) => {}
+    }
+  }
+  for (key, value) in previous_flat {
+    if !current_flat.contains_key(&key) {
+      changes.push(NacosConfigItemChange {
+        key,
+        change_type: ConfigChangeType::Removed,
+        previous: Some(value),
+        current: None,
+      });
+    }
   }
+  Some(changes)
 }
 
 fn transfer_conf_resp(config_resp: nacos_sdk::api::config::ConfigResponse) -> NacosConfigResponse {
@@ -208,5 +1821,7 @@ fn transfer_conf_resp(config_resp: nacos_sdk::api::config::ConfigResponse) -> Na
     content: config_resp.content().to_string(),
     content_type: config_resp.content_type().to_string(),
     md5: config_resp.md5().to_string(),
+    is_beta: None,
+    stale: false,
   }
 }
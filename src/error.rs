@@ -0,0 +1,13 @@
+use napi::{Env, Error, JsObject, Result};
+
+/// Create a js Error with `code`, so that the caller can tell it by `err.code`, more properties can be set on it.
+pub(crate) fn create_coded_error(env: &Env, code: &str, message: String) -> Result<JsObject> {
+  let mut error = env.create_error(Error::from_reason(message))?;
+  error.set_named_property("code", env.create_string(code)?)?;
+  Ok(error)
+}
+
+/// Throw (or reject with) the js Error as it is.
+pub(crate) fn throw_js_error(error: JsObject) -> Error {
+  Error::from(error.into_unknown())
+}
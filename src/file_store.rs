@@ -0,0 +1,725 @@
+use nacos_sdk::api::config::{ConfigChangeListener, ConfigResponse, ConfigService};
+use nacos_sdk::api::error::{Error as NacosError, Result as NacosResult};
+use nacos_sdk::api::plugin::{ConfigFilter, ConfigReq, ConfigResp};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::Duration;
+
+/// Scheme of serverAddr which serves the configs from the local dir, e.g. `file:///path/to/configs`.
+pub(crate) const FILE_SCHEME: &str = "file://";
+
+/// The files of configs listened are checked for changes at this interval.
+const FILE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Content's type of the config published without one, the same as nacos server.
+const DEFAULT_CONTENT_TYPE: &str = "text";
+
+/// Dir in each dir of group, which keeps the meta and temp files of the store apart from the configs.
+/// No config is named by it, since the encoded names never start with `.`, see [`crate::encode_name`].
+const RESERVED_DIR: &str = ".nacos";
+
+/// Where the configs come from, nacos server by nacos-sdk, or the local dir by `file://` serverAddr.
+#[derive(Clone)]
+pub(crate) enum ConfigBackend {
+  Nacos(ConfigService),
+  File(Arc<FileConfigStore>),
+}
+
+impl ConfigBackend {
+  pub(crate) async fn get_config(
+    &self,
+    data_id: String,
+    group: String,
+  ) -> NacosResult<ConfigResponse> {
+    match self {
+      ConfigBackend::Nacos(inner) => inner.get_config(data_id, group).await,
+      ConfigBackend::File(store) => store.get_config(data_id, group).await,
+    }
+  }
+
+  pub(crate) async fn publish_config(
+    &self,
+    data_id: String,
+    group: String,
+    content: String,
+    content_type: Option<String>,
+  ) -> NacosResult<bool> {
+    match self {
+      ConfigBackend::Nacos(inner) => {
+        inner
+          .publish_config(data_id, group, content, content_type)
+          .await
+      }
+      ConfigBackend::File(store) => {
+        store
+          .publish_config(data_id, group, content, content_type, None)
+          .await
+      }
+    }
+  }
+
+  pub(crate) async fn publish_config_cas(
+    &self,
+    data_id: String,
+    group: String,
+    content: String,
+    content_type: Option<String>,
+    cas_md5: String,
+  ) -> NacosResult<bool> {
+    match self {
+      ConfigBackend::Nacos(inner) => {
+        inner
+          .publish_config_cas(data_id, group, content, content_type, cas_md5)
+          .await
+      }
+      ConfigBackend::File(store) => {
+        check_not_blank(&cas_md5, "cas_md5")?;
+        store
+          .publish_config(data_id, group, content, content_type, Some(cas_md5))
+          .await
+      }
+    }
+  }
+
+  pub(crate) async fn publish_config_beta(
+    &self,
+    data_id: String,
+    group: String,
+    content: String,
+    content_type: Option<String>,
+    beta_ips: String,
+  ) -> NacosResult<bool> {
+    match self {
+      ConfigBackend::Nacos(inner) => {
+        inner
+          .publish_config_beta(data_id, group, content, content_type, beta_ips)
+          .await
+      }
+      ConfigBackend::File(_) => Err(NacosError::ErrResult(
+        "beta config is not supported by file serverAddr".to_string(),
+      )),
+    }
+  }
+
+  /// Publish config with params, only the content's type is kept by the file one.
+  pub(crate) async fn publish_config_param(
+    &self,
+    data_id: String,
+    group: String,
+    content: String,
+    content_type: Option<String>,
+    cas_md5: Option<String>,
+    params: HashMap<String, String>,
+  ) -> NacosResult<bool> {
+    match self {
+      ConfigBackend::Nacos(inner) => {
+        inner
+          .publish_config_param(data_id, group, content, content_type, cas_md5, params)
+          .await
+      }
+      ConfigBackend::File(store) => {
+        store
+          .publish_config(data_id, group, content, content_type, cas_md5)
+          .await
+      }
+    }
+  }
+
+  pub(crate) async fn remove_config(&self, data_id: String, group: String) -> NacosResult<bool> {
+    match self {
+      ConfigBackend::Nacos(inner) => inner.remove_config(data_id, group).await,
+      ConfigBackend::File(store) => store.remove_config(data_id, group).await,
+    }
+  }
+
+  pub(crate) async fn add_listener(
+    &self,
+    data_id: String,
+    group: String,
+    listener: Arc<dyn ConfigChangeListener>,
+  ) -> NacosResult<()> {
+    match self {
+      ConfigBackend::Nacos(inner) => inner.add_listener(data_id, group, listener).await,
+      ConfigBackend::File(store) => store.add_listener(data_id, group, listener).await,
+    }
+  }
+
+  pub(crate) async fn remove_listener(
+    &self,
+    data_id: String,
+    group: String,
+    listener: Arc<dyn ConfigChangeListener>,
+  ) -> NacosResult<()> {
+    match self {
+      ConfigBackend::Nacos(inner) => inner.remove_listener(data_id, group, listener).await,
+      ConfigBackend::File(store) => store.remove_listener(data_id, group, &listener),
+    }
+  }
+}
+
+/// Configs in the local dir, laid out as `namespace/group/dataId` (the namespace '' is `public`),
+/// the names are percent-encoded except `[A-Za-z0-9._-]` and a leading `.`. Each file is the content of config,
+/// the content's type and encryptedDataKey published, if any, are kept in `.nacos/{dataId}.meta` of the group.
+/// The config filters are applied the same as nacos-sdk, and the files listened are polled for changes.
+pub(crate) struct FileConfigStore {
+  /// The dir of serverAddr, which has the dirs of namespaces.
+  root: PathBuf,
+  /// The files of namespace, read and written off the async threads.
+  files: Arc<ConfigFiles>,
+  namespace: String,
+  filters: Vec<Box<dyn ConfigFilter>>,
+  watched: Mutex<HashMap<(String, String), WatchedConfig>>,
+  /// Held while checking for changes, so that each change is notified once.
+  checking: tokio::sync::Mutex<()>,
+}
+
+/// The files of configs in the dir of namespace, only touched on the blocking threads.
+struct ConfigFiles {
+  dir: PathBuf,
+  /// Written while writing, so that the cas publish compares and writes at once,
+  /// and read while reading, so that the content is not read with the meta of another publish.
+  lock: RwLock<()>,
+  /// Suffix of the temp files, so that concurrent writes of the same config do not clash.
+  seq: AtomicU64,
+}
+
+struct WatchedConfig {
+  listeners: Vec<Arc<dyn ConfigChangeListener>>,
+  /// Md5 of the content last notified, empty if the config does not exist.
+  md5: String,
+  /// Stamp of the files last read, they are read again only if it changes.
+  stamp: FileStamp,
+  /// Stamp of the files changed since the last poll, they are read once it stays the same for a poll,
+  /// so that the file being written by others is not read half-written.
+  changing: Option<FileStamp>,
+}
+
+/// Modified time and length of the file of config and its meta, None if the file does not exist.
+#[derive(Clone, PartialEq)]
+struct FileStamp([Option<(std::time::SystemTime, u64)>; 2]);
+
+/// The config stored in file, before the config filters.
+struct StoredConfig {
+  content: String,
+  content_type: String,
+  encrypted_data_key: String,
+  md5: String,
+}
+
+impl FileConfigStore {
+  /// Serve the configs of namespace from the dir of `file://` serverAddr, and poll the files listened.
+  pub(crate) fn new(
+    server_addr: &str,
+    namespace: &str,
+    filters: Vec<Box<dyn ConfigFilter>>,
+  ) -> napi::Result<Arc<Self>> {
+    let path = server_addr.trim_start_matches(FILE_SCHEME);
+    // `file:///C:/configs` on windows
+    let path = match path.as_bytes() {
+      [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => &path[1..],
+      _ => path,
+    };
+    if path.is_empty() {
+      return Err(napi::Error::from_reason(format!(
+        "no dir of file serverAddr {server_addr}"
+      )));
+    }
+    let root = PathBuf::from(path);
+    let store = Arc::new(FileConfigStore {
+      files: Arc::new(ConfigFiles {
+        dir: namespace_dir(&root, namespace),
+        lock: RwLock::new(()),
+        seq: AtomicU64::new(0),
+      }),
+      root,
+      namespace: namespace.to_string(),
+      filters,
+      watched: Mutex::new(HashMap::new()),
+      checking: tokio::sync::Mutex::new(()),
+    });
+
+    let weak = Arc::downgrade(&store);
+    napi::bindgen_prelude::spawn(poll_changes(weak));
+    Ok(store)
+  }
+
+  fn path(&self, data_id: &str, group: &str) -> PathBuf {
+    config_path(&self.files.dir, data_id, group)
+  }
+
+  /// Read the config from file off the async threads, None if it does not exist.
+  async fn read(&self, data_id: &str, group: &str) -> std::io::Result<Option<StoredConfig>> {
+    let (files, data_id, group) = (self.files.clone(), data_id.to_string(), group.to_string());
+    tokio::task::spawn_blocking(move || {
+      let _reading = files.lock.read().unwrap();
+      read_config(&files.dir, &data_id, &group)
+    })
+    .await
+    .map_err(std::io::Error::other)?
+  }
+
+  /// Read the config from file with the stamp of files after read off the async threads, None if it does not exist.
+  async fn read_stamped(
+    &self,
+    data_id: &str,
+    group: &str,
+  ) -> std::io::Result<(Option<StoredConfig>, FileStamp)> {
+    let (files, data_id, group) = (self.files.clone(), data_id.to_string(), group.to_string());
+    tokio::task::spawn_blocking(move || {
+      let _reading = files.lock.read().unwrap();
+      let stored = read_config(&files.dir, &data_id, &group)?;
+      Ok((stored, stat_config(&files.dir, &data_id, &group)))
+    })
+    .await
+    .map_err(std::io::Error::other)?
+  }
+
+  /// List the configs of namespace off the async threads, with the content as stored, e.g. encrypted,
+  /// in the order of group and dataId.
+  pub(crate) async fn list_configs(
+    &self,
+    namespace: &str,
+  ) -> std::io::Result<Vec<crate::NacosConfigBundleItem>> {
+    let dir = namespace_dir(&self.root, namespace);
+    tokio::task::spawn_blocking(move || list_configs(&dir))
+      .await
+      .map_err(std::io::Error::other)?
+  }
+
+  /// The config got, after the config filters.
+  async fn respond(&self, data_id: String, group: String, stored: StoredConfig) -> ConfigResponse {
+    let mut config_resp = ConfigResp::new(
+      data_id,
+      group,
+      self.namespace.clone(),
+      stored.content,
+      stored.encrypted_data_key,
+    );
+    for filter in self.filters.iter() {
+      filter.filter(None, Some(&mut config_resp)).await;
+    }
+    ConfigResponse::new(
+      config_resp.data_id,
+      config_resp.group,
+      config_resp.namespace,
+      config_resp.content,
+      stored.content_type,
+      stored.md5,
+    )
+  }
+
+  async fn get_config(&self, data_id: String, group: String) -> NacosResult<ConfigResponse> {
+    check_not_blank(&data_id, "data_id")?;
+    check_not_blank(&group, "group")?;
+    match self.read(&data_id, &group).await {
+      Ok(Some(stored)) => Ok(self.respond(data_id, group, stored).await),
+      Ok(None) => Err(NacosError::ConfigNotFound(format!(
+        "no file {}",
+        self.path(&data_id, &group).display()
+      ))),
+      Err(io_err) => Err(NacosError::ErrResult(io_err.to_string())),
+    }
+  }
+
+  /// Write the config after the config filters, only if the md5 of file is `cas_md5` if any.
+  async fn publish_config(
+    &self,
+    data_id: String,
+    group: String,
+    content: String,
+    content_type: Option<String>,
+    cas_md5: Option<String>,
+  ) -> NacosResult<bool> {
+    check_not_blank(&data_id, "data_id")?;
+    check_not_blank(&group, "group")?;
+    check_not_blank(&content, "content")?;
+    let mut config_req = ConfigReq::new(
+      data_id,
+      group,
+      self.namespace.clone(),
+      content,
+      String::new(),
+    );
+    for filter in self.filters.iter() {
+      filter.filter(Some(&mut config_req), None).await;
+    }
+    // the same as nacos server, e.g. the content is cleared by the config filters failing closed
+    if config_req.content.is_empty() {
+      return Err(NacosError::ErrResult("content invalid".to_string()));
+    }
+
+    let mut meta = serde_json::Map::new();
+    if let Some(content_type) = content_type {
+      meta.insert("type".to_string(), content_type.into());
+    }
+    if !config_req.encrypted_data_key.is_empty() {
+      meta.insert(
+        "encryptedDataKey".to_string(),
+        config_req.encrypted_data_key.into(),
+      );
+    }
+    let files = self.files.clone();
+    tokio::task::spawn_blocking(move || {
+      files.write_config(
+        &config_req.data_id,
+        &config_req.group,
+        &config_req.content,
+        meta,
+        cas_md5,
+      )
+    })
+    .await
+    .map_err(|join_err| NacosError::ErrResult(join_err.to_string()))??;
+    // notify the listeners at once, rather than the next poll
+    self.check_changes(true).await;
+    Ok(true)
+  }
+
+  async fn remove_config(&self, data_id: String, group: String) -> NacosResult<bool> {
+    check_not_blank(&data_id, "data_id")?;
+    check_not_blank(&group, "group")?;
+    let files = self.files.clone();
+    tokio::task::spawn_blocking(move || files.remove_config(&data_id, &group))
+      .await
+      .map_err(|join_err| NacosError::ErrResult(join_err.to_string()))??;
+    self.check_changes(true).await;
+    Ok(true)
+  }
+
+  /// Listen to the file of config, the listener is notified once its content changes from now on.
+  async fn add_listener(
+    &self,
+    data_id: String,
+    group: String,
+    listener: Arc<dyn ConfigChangeListener>,
+  ) -> NacosResult<()> {
+    check_not_blank(&data_id, "data_id")?;
+    check_not_blank(&group, "group")?;
+    let (md5, stamp) = match self.read_stamped(&data_id, &group).await {
+      Ok((stored, stamp)) => (stored.map(|stored| stored.md5).unwrap_or_default(), stamp),
+      Err(io_err) => return Err(NacosError::ErrResult(io_err.to_string())),
+    };
+    self
+      .watched
+      .lock()
+      .unwrap()
+      .entry((data_id, group))
+      .or_insert(WatchedConfig {
+        listeners: Vec::new(),
+        md5,
+        stamp,
+        changing: None,
+      })
+      .listeners
+      .push(listener);
+    Ok(())
+  }
+
+  fn remove_listener(
+    &self,
+    data_id: String,
+    group: String,
+    listener: &Arc<dyn ConfigChangeListener>,
+  ) -> NacosResult<()> {
+    check_not_blank(&data_id, "data_id")?;
+    check_not_blank(&group, "group")?;
+    let mut watched = self.watched.lock().unwrap();
+    let key = (data_id, group);
+    if let Some(config) = watched.get_mut(&key) {
+      config
+        .listeners
+        .retain(|config_listener| !Arc::ptr_eq(config_listener, listener));
+      if config.listeners.is_empty() {
+        watched.remove(&key);
+      }
+    }
+    Ok(())
+  }
+
+  /// Notify the listeners of the configs changed, the removed one is notified with empty content as nacos-sdk.
+  /// Only the files whose stamp changes are read, at once if `written` by the store as it writes atomically,
+  /// or once the stamp stays the same for a poll.
+  async fn check_changes(&self, written: bool) {
+    let _checking = self.checking.lock().await;
+    let keys: Vec<_> = self.watched.lock().unwrap().keys().cloned().collect();
+    let dir = self.files.dir.clone();
+    let Ok(stamps) = tokio::task::spawn_blocking(move || {
+      keys
+        .into_iter()
+        .map(|(data_id, group)| {
+          let stamp = stat_config(&dir, &data_id, &group);
+          (data_id, group, stamp)
+        })
+        .collect::<Vec<_>>()
+    })
+    .await
+    else {
+      return;
+    };
+
+    for (data_id, group, stamp) in stamps {
+      {
+        let mut watched = self.watched.lock().unwrap();
+        let Some(config) = watched.get_mut(&(data_id.clone(), group.clone())) else {
+          continue;
+        };
+        if config.stamp == stamp {
+          config.changing = None;
+          continue;
+        }
+        if !written && config.changing.as_ref() != Some(&stamp) {
+          config.changing = Some(stamp);
+          continue;
+        }
+      }
+      // try again on the next poll
+      let Ok((stored, stamp_read)) = self.read_stamped(&data_id, &group).await else {
+        continue;
+      };
+      let md5 = stored
+        .as_ref()
+        .map(|stored| stored.md5.clone())
+        .unwrap_or_default();
+      let listeners = {
+        let mut watched = self.watched.lock().unwrap();
+        match watched.get_mut(&(data_id.clone(), group.clone())) {
+          // changed while it is read
+          Some(config) if stamp_read != stamp => {
+            config.changing = Some(stamp_read);
+            continue;
+          }
+          Some(config) => {
+            config.stamp = stamp;
+            config.changing = None;
+            if config.md5 == md5 {
+              continue;
+            }
+            config.md5 = md5;
+            config.listeners.clone()
+          }
+          None => continue,
+        }
+      };
+      let config_resp = match stored {
+        Some(stored) => self.respond(data_id, group, stored).await,
+        None => ConfigResponse::new(
+          data_id,
+          group,
+          self.namespace.clone(),
+          String::new(),
+          DEFAULT_CONTENT_TYPE.to_string(),
+          String::new(),
+        ),
+      };
+      for listener in listeners {
+        listener.notify(config_resp.clone());
+      }
+    }
+  }
+}
+
+impl ConfigFiles {
+  /// Write the content of config and then its meta, only if the md5 of file is `cas_md5` if any.
+  fn write_config(
+    &self,
+    data_id: &str,
+    group: &str,
+    content: &str,
+    meta: serde_json::Map<String, serde_json::Value>,
+    cas_md5: Option<String>,
+  ) -> NacosResult<()> {
+    let _writing = self.lock.write().unwrap();
+    if let Some(cas_md5) = cas_md5 {
+      let stored = read_config(&self.dir, data_id, group)
+        .map_err(|io_err| NacosError::ErrResult(io_err.to_string()))?;
+      if stored.map(|stored| stored.md5) != Some(cas_md5) {
+        return Err(NacosError::ErrResult(
+          "Cas publish fail, server md5 may have changed.".to_string(),
+        ));
+      }
+    }
+    let meta_path = meta_path(&self.dir, data_id, group);
+    self
+      .write(config_path(&self.dir, data_id, group), content, group)
+      .and_then(|_| {
+        if meta.is_empty() {
+          remove_file(&meta_path)
+        } else {
+          self.write(
+            meta_path,
+            &serde_json::Value::Object(meta).to_string(),
+            group,
+          )
+        }
+      })
+      .map_err(|io_err| NacosError::ErrResult(io_err.to_string()))
+  }
+
+  /// Write the file atomically, by a temp file in the reserved dir of group renamed to it.
+  fn write(&self, path: PathBuf, content: &str, group: &str) -> std::io::Result<()> {
+    let reserved = reserved_dir(&self.dir, group);
+    let tmp = reserved.join(format!(
+      "{}-{}.tmp",
+      std::process::id(),
+      self.seq.fetch_add(1, Ordering::Relaxed)
+    ));
+    let written = std::fs::create_dir_all(&reserved)
+      .and_then(|_| std::fs::write(&tmp, content))
+      .and_then(|_| std::fs::rename(&tmp, &path));
+    if written.is_err() {
+      let _ = std::fs::remove_file(&tmp);
+    }
+    written
+  }
+
+  fn remove_config(&self, data_id: &str, group: &str) -> NacosResult<()> {
+    let _writing = self.lock.write().unwrap();
+    for path in [
+      config_path(&self.dir, data_id, group),
+      meta_path(&self.dir, data_id, group),
+    ] {
+      remove_file(&path).map_err(|io_err| NacosError::ErrResult(io_err.to_string()))?;
+    }
+    Ok(())
+  }
+}
+
+/// Remove the file, which may not exist.
+fn remove_file(path: &Path) -> std::io::Result<()> {
+  match std::fs::remove_file(path) {
+    Err(io_err) if io_err.kind() != std::io::ErrorKind::NotFound => Err(io_err),
+    _ => Ok(()),
+  }
+}
+
+/// The dir of namespace, the namespace '' is `public`.
+fn namespace_dir(root: &Path, namespace: &str) -> PathBuf {
+  root.join(crate::encode_name(if namespace.is_empty() {
+    "public"
+  } else {
+    namespace
+  }))
+}
+
+fn config_path(dir: &Path, data_id: &str, group: &str) -> PathBuf {
+  dir
+    .join(crate::encode_name(group))
+    .join(crate::encode_name(data_id))
+}
+
+fn reserved_dir(dir: &Path, group: &str) -> PathBuf {
+  dir.join(crate::encode_name(group)).join(RESERVED_DIR)
+}
+
+fn meta_path(dir: &Path, data_id: &str, group: &str) -> PathBuf {
+  reserved_dir(dir, group).join(format!("{}.meta", crate::encode_name(data_id)))
+}
+
+/// Stamp of the files of config in the dir of namespace.
+fn stat_config(dir: &Path, data_id: &str, group: &str) -> FileStamp {
+  let stat = |path: PathBuf| {
+    std::fs::metadata(path)
+      .ok()
+      .map(|meta| (meta.modified().unwrap_or(std::time::UNIX_EPOCH), meta.len()))
+  };
+  FileStamp([
+    stat(config_path(dir, data_id, group)),
+    stat(meta_path(dir, data_id, group)),
+  ])
+}
+
+/// Read the config in the dir of namespace, None if it does not exist.
+fn read_config(dir: &Path, data_id: &str, group: &str) -> std::io::Result<Option<StoredConfig>> {
+  let content = match std::fs::read_to_string(config_path(dir, data_id, group)) {
+    Ok(content) => content,
+    Err(io_err) if io_err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+    Err(io_err) => return Err(io_err),
+  };
+  let meta: serde_json::Value = std::fs::read_to_string(meta_path(dir, data_id, group))
+    .ok()
+    .and_then(|meta| serde_json::from_str(&meta).ok())
+    .unwrap_or_default();
+  let field = |name: &str| {
+    meta
+      .get(name)
+      .and_then(serde_json::Value::as_str)
+      .map(str::to_string)
+  };
+  Ok(Some(StoredConfig {
+    md5: format!("{:x}", md5::compute(&content)),
+    content,
+    content_type: field("type").unwrap_or(DEFAULT_CONTENT_TYPE.to_string()),
+    encrypted_data_key: field("encryptedDataKey").unwrap_or_default(),
+  }))
+}
+
+/// List the configs in the dir of namespace, in the order of group and dataId.
+fn list_configs(dir: &Path) -> std::io::Result<Vec<crate::NacosConfigBundleItem>> {
+  let mut configs = Vec::new();
+  for group in list_names(dir, true)? {
+    for data_id in list_names(&dir.join(crate::encode_name(&group)), false)? {
+      let Some(stored) = read_config(dir, &data_id, &group)? else {
+        continue;
+      };
+      configs.push(crate::NacosConfigBundleItem {
+        data_id,
+        group: group.clone(),
+        content: stored.content,
+        content_type: stored.content_type,
+        md5: stored.md5,
+        encrypted_data_key: Some(stored.encrypted_data_key).filter(|key| !key.is_empty()),
+      });
+    }
+  }
+  Ok(configs)
+}
+
+/// Decoded names of the dirs (of groups) or files (of configs) in the dir sorted, empty if the dir does not exist.
+/// The hidden ones are not encoded by [`crate::encode_name`], e.g. the reserved dir, or the files of other tools.
+fn list_names(dir: &Path, dirs: bool) -> std::io::Result<Vec<String>> {
+  let entries = match std::fs::read_dir(dir) {
+    Ok(entries) => entries,
+    Err(io_err) if io_err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+    Err(io_err) => return Err(io_err),
+  };
+  let mut names = Vec::new();
+  for entry in entries {
+    let entry = entry?;
+    // following the links
+    if entry.path().is_dir() != dirs {
+      continue;
+    }
+    let name = entry.file_name();
+    if let Some(name) = name.to_str().filter(|name| !name.starts_with('.'))
+      && let Some(name) = crate::decode_name(name)
+    {
+      names.push(name);
+    }
+  }
+  names.sort();
+  Ok(names)
+}
+
+/// Poll the files listened until the store is dropped.
+async fn poll_changes(store: Weak<FileConfigStore>) {
+  loop {
+    tokio::time::sleep(FILE_POLL_INTERVAL).await;
+    let Some(store) = store.upgrade() else {
+      return;
+    };
+    store.check_changes(false).await;
+  }
+}
+
+/// The same check of params as nacos-sdk.
+fn check_not_blank(value: &str, name: &str) -> NacosResult<()> {
+  if value.trim().is_empty() {
+    return Err(NacosError::InvalidParam(
+      name.to_string(),
+      "param must not blank!".to_string(),
+    ));
+  }
+  Ok(())
+}
@@ -0,0 +1,178 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Format of config, the content's type if it is structured, otherwise the extension of dataId.
+pub(crate) fn config_format(content_type: &str, data_id: &str) -> String {
+  let content_type = content_type.to_ascii_lowercase();
+  if is_structured(&content_type) {
+    return content_type;
+  }
+  match data_id.rsplit_once('.') {
+    Some((_, ext)) if is_structured(&ext.to_ascii_lowercase()) => ext.to_ascii_lowercase(),
+    _ => content_type,
+  }
+}
+
+fn is_structured(format: &str) -> bool {
+  matches!(format, "json" | "yaml" | "yml" | "properties" | "toml")
+}
+
+/// Why the content can not be parsed, line and column are 1-based.
+pub(crate) struct ParseError {
+  pub(crate) message: String,
+  pub(crate) line: Option<u32>,
+  pub(crate) column: Option<u32>,
+}
+
+/// Parse the content as a structured value by format, None if the format is not structured.
+pub(crate) fn parse_structured(
+  format: &str,
+  content: &str,
+) -> Option<std::result::Result<Value, ParseError>> {
+  match format {
+    "json" => Some(serde_json::from_str(content).map_err(|err| ParseError {
+      message: err.to_string(),
+      line: Some(err.line() as u32),
+      column: Some(err.column() as u32),
+    })),
+    "yaml" | "yml" => Some(serde_yaml::from_str(content).map_err(|err| {
+      let location = err.location();
+      ParseError {
+        message: err.to_string(),
+        line: location.as_ref().map(|l| l.line() as u32),
+        column: location.as_ref().map(|l| l.column() as u32),
+      }
+    })),
+    "properties" => Some(Ok(parse_properties(content))),
+    "toml" => Some(parse_toml(content)),
+    _ => None,
+  }
+}
+
+fn parse_toml(content: &str) -> std::result::Result<Value, ParseError> {
+  let value: toml::Value = toml::from_str(content).map_err(|err| {
+    let (line, column) = match err.span() {
+      Some(span) => {
+        let (line, column) = line_column(content, span.start);
+        (Some(line), Some(column))
+      }
+      None => (None, None),
+    };
+    ParseError {
+      message: err.message().to_string(),
+      line,
+      column,
+    }
+  })?;
+  Ok(toml_to_json(value))
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+  match value {
+    toml::Value::String(s) => Value::String(s),
+    toml::Value::Integer(i) => Value::from(i),
+    toml::Value::Float(f) => Value::from(f),
+    toml::Value::Boolean(b) => Value::Bool(b),
+    toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+    toml::Value::Array(list) => Value::Array(list.into_iter().map(toml_to_json).collect()),
+    toml::Value::Table(table) => Value::Object(
+      table
+        .into_iter()
+        .map(|(key, value)| (key, toml_to_json(value)))
+        .collect(),
+    ),
+  }
+}
+
+/// 1-based line and column of the byte offset.
+fn line_column(content: &str, offset: usize) -> (u32, u32) {
+  let before = &content[..offset.min(content.len())];
+  let line = before.matches('\n').count() + 1;
+  let column = before
+    .rsplit('\n')
+    .next()
+    .unwrap_or_default()
+    .chars()
+    .count()
+    + 1;
+  (line as u32, column as u32)
+}
+
+/// Parse java properties into a flat object of string values.
+pub(crate) fn parse_properties(content: &str) -> Value {
+  let mut map = serde_json::Map::new();
+  let mut lines = content.lines();
+  while let Some(line) = lines.next() {
+    let mut line = line.trim_start().to_string();
+    if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+      continue;
+    }
+    // a line ends with odd backslashes continues on the next line
+    while line.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1 {
+      line.pop();
+      match lines.next() {
+        Some(next) => line.push_str(next.trim_start()),
+        None => break,
+      }
+    }
+
+    let split = line.find(['=', ':']).unwrap_or(line.len());
+    let (key, value) = line.split_at(split);
+    let value = value.get(1..).unwrap_or_default();
+    map.insert(
+      key.trim().to_string(),
+      Value::String(value.trim().to_string()),
+    );
+  }
+  Value::Object(map)
+}
+
+/// Flatten a structured value into keys like `a.b[0].c`, scalar is rendered as string.
+pub(crate) fn flatten(value: &Value) -> BTreeMap<String, String> {
+  let mut flat = BTreeMap::new();
+  flatten_into(value, String::new(), &mut flat);
+  flat
+}
+
+fn flatten_into(value: &Value, prefix: String, flat: &mut BTreeMap<String, String>) {
+  match value {
+    Value::Object(map) if !map.is_empty() => {
+      for (key, value) in map {
+        let key = if prefix.is_empty() {
+          key.clone()
+        } else {
+          format!("{prefix}.{key}")
+        };
+        flatten_into(value, key, flat);
+      }
+    }
+    Value::Array(list) if !list.is_empty() => {
+      for (idx, value) in list.iter().enumerate() {
+        flatten_into(value, format!("{prefix}[{idx}]"), flat);
+      }
+    }
+    Value::String(s) => {
+      flat.insert(prefix, s.clone());
+    }
+    other => {
+      flat.insert(prefix, other.to_string());
+    }
+  }
+}
+
+/// Deep merge the overlay into the base, objects are merged by key, others are replaced by the overlay.
+pub(crate) fn deep_merge(base: &mut Value, overlay: Value) {
+  match (base, overlay) {
+    (Value::Object(base), Value::Object(overlay)) => {
+      for (key, value) in overlay {
+        match base.get_mut(&key) {
+          Some(base_value) => deep_merge(base_value, value),
+          None => {
+            base.insert(key, value);
+          }
+        }
+      }
+    }
+    (base, overlay) => *base = overlay,
+  }
+}
@@ -20,9 +20,10 @@ pub fn get_runtime() -> &'static tokio::runtime::Runtime {
   })
 }
 
-#[napi(object)]
+#[napi(object, object_to_js = false)]
 pub struct ClientOptions {
   /// Server Addr, e.g. address:port[,address:port],...]
+  /// or `file:///path/to/configs` for NacosConfigClient, which serves the configs from the dir without nacos server
   pub server_addr: String,
   /// Namespace/Tenant
   pub namespace: String,
@@ -44,13 +45,78 @@ pub struct ClientOptions {
   pub naming_load_cache_at_start: Option<bool>,
   /// config load_cache_at_start, default false
   pub config_load_cache_at_start: Option<bool>,
+  /// config `isBeta` checked by the http open api of nacos server, default false that `isBeta` is absent.
+  /// It costs a request for each config got or notified, and it is only a hint, as nacos-sdk does not tell it:
+  /// the server judges it by the ip of http request, which may not be the one of grpc, e.g. behind a proxy
+  pub config_check_beta: Option<bool>,
+  /// config placeholders `${name}` expanded by variables, env vars and keys of other configs, default disabled
+  pub config_placeholders: Option<PlaceholderOptions>,
+  /// config built-in cipher of the configs whose dataId starts with `cipher-aes-`, default disabled.
+  /// The same format as the AES encryption plugin of nacos, so the configs are shared with the java clients of the same key
+  pub config_cipher: Option<ConfigCipherOptions>,
+  /// config content passes through as it is if config filters fail, default false that fails closed:
+  /// getConfig and publishConfig reject with code 'ConfigFilterError', and the listeners skip the change
+  pub config_filter_pass_through: Option<bool>,
+  /// config filters (and the key provider of cipher) fail if they take longer, in milliseconds, default 3000
+  pub config_filter_timeout_ms: Option<u32>,
+  /// config snapshots are written to the dir atomically on every successful get and notify, default disabled.
+  /// They are the contents before config filters, e.g. encrypted, which are filtered again when read
+  pub config_snapshot_dir: Option<String>,
+  /// config read from the snapshots, 'off', 'onError' (the server can not be reached in 3s) or 'always', default 'onError'.
+  /// The config read from the snapshot is `stale`, and in 'always' the snapshot is refreshed by the server in background
+  #[napi(ts_type = "'off' | 'onError' | 'always'")]
+  pub config_failover_mode: Option<String>,
 }
 
+mod bundle;
+pub use bundle::*;
+
+mod cipher;
+pub use cipher::*;
+
+mod compose;
+pub use compose::*;
+
 mod config;
 pub use config::*;
 
+mod error;
+pub(crate) use error::*;
+
+mod file_store;
+pub(crate) use file_store::*;
+
+mod format;
+pub(crate) use format::*;
+
+#[cfg(feature = "mock")]
+mod mock;
+#[cfg(feature = "mock")]
+pub use mock::*;
+
 mod naming;
 pub use naming::*;
 
+mod open_api;
+pub(crate) use open_api::*;
+
+mod placeholder;
+pub use placeholder::*;
+
 mod plugin;
 pub use plugin::*;
+
+mod registry;
+pub(crate) use registry::*;
+
+mod schema;
+pub use schema::*;
+
+mod snapshot;
+pub(crate) use snapshot::*;
+
+mod subscription;
+pub use subscription::*;
+
+mod watch;
+pub use watch::*;
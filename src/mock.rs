@@ -0,0 +1,1001 @@
+use serde_json::{Value, json};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, watch};
+use tonic::codegen::*;
+
+/// Start a mock of nacos server on a random port of 127.0.0.1, for tests without nacos server.
+/// It is only in the builds with the cargo feature `mock`, e.g. `yarn build:mock`, not in the released ones,
+/// so its typings are generated to `mock.d.ts` instead of the published `index.d.ts`, and it is `undefined` there.
+/// If it fails, pay attention to err
+#[napi]
+pub async fn start_mock_nacos() -> napi::Result<MockNacos> {
+  MockNacos::start()
+    .await
+    .map_err(|io_err| napi::Error::from_reason(format!("start mock nacos failed: {io_err}")))
+}
+
+/// A local mock of nacos server, which serves the config and naming api of nacos-sdk by grpc,
+/// and the beta config, list configs and listener query api by http. The configs and instances are kept in memory only.
+/// Pass its `serverAddr` to NacosConfigClient or NacosNamingClient, and close it after tests.
+#[napi]
+pub struct MockNacos {
+  server_addr: String,
+  state: Arc<Mutex<MockState>>,
+  shutdown: watch::Sender<bool>,
+}
+
+#[napi]
+impl MockNacos {
+  /// Server Addr of the mock, e.g. `127.0.0.1:port`, the grpc port is the port + 1000 as nacos server.
+  #[napi(getter)]
+  pub fn server_addr(&self) -> String {
+    self.server_addr.clone()
+  }
+
+  /// Stop serving and disconnect the clients, call it more than once is fine.
+  #[napi]
+  pub fn close(&self) {
+    self.shutdown.send_replace(true);
+    // end the bi streams, so that the grpc connections can be closed
+    self.state.lock().unwrap().connections.clear();
+  }
+}
+
+impl MockNacos {
+  /// Start the mock on a random port, the grpc and http api are served until it is closed.
+  pub async fn start() -> std::io::Result<MockNacos> {
+    let (http_listener, grpc_listener) = loop {
+      let http_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+      let http_port = http_listener.local_addr()?.port();
+      if http_port > u16::MAX - GRPC_PORT_OFFSET {
+        continue;
+      }
+      if let Ok(grpc_listener) =
+        tokio::net::TcpListener::bind(("127.0.0.1", http_port + GRPC_PORT_OFFSET)).await
+      {
+        break (http_listener, grpc_listener);
+      }
+    };
+    let server_addr = format!("127.0.0.1:{}", http_listener.local_addr()?.port());
+    let server = MockServer::default();
+    let (shutdown, _) = watch::channel(false);
+
+    let mut grpc_shutdown = shutdown.subscribe();
+    let (request, bi_stream) = (
+      RequestServer(server.clone()),
+      BiRequestStreamServer(server.clone()),
+    );
+    tokio::spawn(async move {
+      let _ = tonic::transport::Server::builder()
+        .add_service(request)
+        .add_service(bi_stream)
+        .serve_with_incoming_shutdown(
+          tokio_stream::wrappers::TcpListenerStream::new(grpc_listener),
+          async move {
+            let _ = grpc_shutdown.wait_for(|closed| *closed).await;
+          },
+        )
+        .await;
+    });
+
+    let mut http_shutdown = shutdown.subscribe();
+    let http_server = server.clone();
+    tokio::spawn(async move {
+      loop {
+        let stream = tokio::select! {
+          accepted = http_listener.accept() => match accepted {
+            Ok((stream, _)) => stream,
+            Err(_) => return,
+          },
+          _ = http_shutdown.wait_for(|closed| *closed) => return,
+        };
+        let server = http_server.clone();
+        tokio::spawn(async move {
+          let _ = server.serve_http(stream).await;
+        });
+      }
+    });
+
+    Ok(MockNacos {
+      server_addr,
+      state: server.state,
+      shutdown,
+    })
+  }
+}
+
+/// The grpc port of nacos server is the http port + 1000.
+const GRPC_PORT_OFFSET: u16 = 1000;
+
+/// Message of the nacos grpc api, the request and response are json in `body`.
+#[derive(Clone, PartialEq, prost::Message)]
+struct Metadata {
+  #[prost(string, tag = "3")]
+  r#type: String,
+  #[prost(string, tag = "8")]
+  client_ip: String,
+  #[prost(map = "string, string", tag = "7")]
+  headers: HashMap<String, String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Payload {
+  #[prost(message, optional, tag = "2")]
+  metadata: Option<Metadata>,
+  #[prost(message, optional, tag = "3")]
+  body: Option<prost_types::Any>,
+}
+
+/// (namespace, group, dataId)
+type ConfigKey = (String, String, String);
+/// (namespace, groupName, serviceName)
+type ServiceKey = (String, String, String);
+type PushSender = mpsc::Sender<std::result::Result<Payload, tonic::Status>>;
+
+#[derive(Clone, Default)]
+struct MockConfig {
+  content: String,
+  md5: String,
+  content_type: String,
+  encrypted_data_key: String,
+  beta_ips: Vec<String>,
+  beta_content: Option<String>,
+}
+
+#[derive(Default)]
+struct MockState {
+  configs: HashMap<ConfigKey, MockConfig>,
+  /// Connections listen to the config.
+  config_listeners: HashMap<ConfigKey, HashSet<String>>,
+  services: HashMap<ServiceKey, Vec<Value>>,
+  /// Connections subscribe the service, with the clusters.
+  service_subscribers: HashMap<ServiceKey, HashSet<(String, String)>>,
+  /// The bi stream of connection, which pushes the changes to the client.
+  connections: HashMap<String, PushSender>,
+  ref_time: i64,
+}
+
+/// Handle the requests of grpc and http, shared by the servers of [`MockNacos`].
+#[derive(Clone, Default)]
+struct MockServer {
+  state: Arc<Mutex<MockState>>,
+}
+
+impl MockServer {
+  /// Serve one request of http, enough for the get config, beta config and list configs api of [`crate::OpenApiClient`],
+  /// and the listener query api of config for tests.
+  async fn serve_http(&self, mut stream: tokio::net::TcpStream) -> std::io::Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    while !buf.windows(4).any(|window| window == b"\r\n\r\n") {
+      let n = stream.read(&mut chunk).await?;
+      if n == 0 {
+        return Ok(());
+      }
+      buf.extend_from_slice(&chunk[..n]);
+    }
+    let head = String::from_utf8_lossy(&buf).to_string();
+    let mut parts = head.lines().next().unwrap_or_default().split(' ');
+    let (method, target) = (
+      parts.next().unwrap_or_default(),
+      parts.next().unwrap_or_default(),
+    );
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let params: HashMap<String, String> = query
+      .split('&')
+      .filter_map(|kv| kv.split_once('='))
+      .map(|(k, v)| (url_decode(k), url_decode(v)))
+      .collect();
+    let is_get_config = method == "GET"
+      && path == "/nacos/v1/cs/configs"
+      && !params.contains_key("beta")
+      && !params.contains_key("search");
+    let (status, headers, body) = if is_get_config {
+      let client_ip = stream
+        .peer_addr()
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_default();
+      match self.get_config_http(&params, &client_ip) {
+        Some((content, is_beta)) => (
+          "200 OK",
+          format!("Content-Type: text/plain\r\nisBeta: {is_beta}\r\n"),
+          content,
+        ),
+        None => (
+          "404 Not Found",
+          "Content-Type: text/plain\r\n".to_string(),
+          "config data not exist".to_string(),
+        ),
+      }
+    } else {
+      (
+        "200 OK",
+        "Content-Type: application/json\r\n".to_string(),
+        self.handle_http(method, path, &params).to_string(),
+      )
+    };
+    let resp = format!(
+      "HTTP/1.1 {status}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+      body.len(),
+    );
+    stream.write_all(resp.as_bytes()).await?;
+    stream.shutdown().await
+  }
+
+  /// Get the config by http, the beta content with `isBeta` true if the client ip is one of the betaIps.
+  fn get_config_http(
+    &self,
+    params: &HashMap<String, String>,
+    client_ip: &str,
+  ) -> Option<(String, bool)> {
+    let param = |key: &str| params.get(key).cloned().unwrap_or_default();
+    let key = (param("tenant"), param("group"), param("dataId"));
+    let state = self.state.lock().unwrap();
+    let conf = state.configs.get(&key)?;
+    match conf.beta_content.as_ref() {
+      Some(beta_content) if conf.beta_ips.iter().any(|ip| ip == client_ip) => {
+        Some((beta_content.clone(), true))
+      }
+      _ => Some((conf.content.clone(), false)),
+    }
+  }
+
+  fn handle_http(&self, method: &str, path: &str, params: &HashMap<String, String>) -> Value {
+    let param = |key: &str| params.get(key).cloned().unwrap_or_default();
+    match (method, path) {
+      ("POST", "/nacos/v1/auth/login") => {
+        json!({ "accessToken": "mock-token", "tokenTtl": 18000, "globalAdmin": true })
+      }
+      (_, "/nacos/v1/cs/configs") if param("beta") == "true" => {
+        let key = (param("tenant"), param("group"), param("dataId"));
+        let mut state = self.state.lock().unwrap();
+        if method == "DELETE" {
+          if let Some(conf) = state.configs.get_mut(&key) {
+            conf.beta_ips.clear();
+            conf.beta_content = None;
+          }
+          notify_config(&state, &key);
+          return json!({ "code": 200, "message": "stop beta ok", "data": true });
+        }
+        let data = state.configs.get(&key).and_then(|conf| {
+          let content = conf.beta_content.clone()?;
+          Some(json!({
+            "dataId": key.2,
+            "group": key.1,
+            "tenant": key.0,
+            "md5": md5_hex(&content),
+            "content": content,
+            "betaIps": conf.beta_ips.join(","),
+          }))
+        });
+        json!({ "code": 200, "message": "query beta ok", "data": data })
+      }
+      ("GET", "/nacos/v1/cs/configs") if param("search") == "blur" => {
+        let page_no: usize = param("pageNo").parse().unwrap_or(1).max(1);
+        let page_size: usize = param("pageSize").parse().unwrap_or(10).max(1);
+        let state = self.state.lock().unwrap();
+        let mut keys: Vec<_> = state
+          .configs
+          .keys()
+          .filter(|key| {
+            key.0 == param("tenant")
+              && blur_match(&param("group"), &key.1)
+              && blur_match(&param("dataId"), &key.2)
+          })
+          .collect();
+        keys.sort();
+        let items: Vec<Value> = keys
+          .iter()
+          .skip((page_no - 1) * page_size)
+          .take(page_size)
+          .map(|key| {
+            let conf = &state.configs[*key];
+            json!({
+              "dataId": key.2,
+              "group": key.1,
+              "tenant": key.0,
+              "content": conf.content,
+              "md5": conf.md5,
+              "type": conf.content_type,
+              "encryptedDataKey": conf.encrypted_data_key,
+            })
+          })
+          .collect();
+        json!({
+          "totalCount": keys.len(),
+          "pageNumber": page_no,
+          "pagesAvailable": keys.len().div_ceil(page_size),
+          "pageItems": items,
+        })
+      }
+      ("GET", "/nacos/v1/cs/configs/listener") => {
+        let key = (param("tenant"), param("group"), param("dataId"));
+        let state = self.state.lock().unwrap();
+        let md5 = state
+          .configs
+          .get(&key)
+          .map(|conf| conf.md5.clone())
+          .unwrap_or_default();
+        let listeners: serde_json::Map<String, Value> = state
+          .config_listeners
+          .get(&key)
+          .into_iter()
+          .flatten()
+          .map(|conn_id| (conn_id.clone(), md5.clone().into()))
+          .collect();
+        // the field is misspelled the same as nacos server
+        json!({ "collectionStatus": 200, "lisentersGroupkeyStatus": listeners })
+      }
+      _ => json!({ "code": 404, "message": "not found", "data": null }),
+    }
+  }
+
+  /// Handle the grpc request of type, return the type of response and its body.
+  fn handle(
+    &self,
+    conn_id: &str,
+    client_ip: &str,
+    r_type: &str,
+    body: Value,
+  ) -> (&'static str, Value) {
+    let rid = body.get("requestId").cloned().unwrap_or(Value::Null);
+    match r_type {
+      "ServerCheckRequest" => (
+        "ServerCheckResponse",
+        ok_response(&rid, json!({ "connectionId": conn_id })),
+      ),
+      "HealthCheckRequest" => ("HealthCheckResponse", ok_response(&rid, json!({}))),
+      "ConfigQueryRequest" => {
+        let key = config_key(&body);
+        let state = self.state.lock().unwrap();
+        let Some(conf) = state.configs.get(&key) else {
+          let mut resp = fail_response(&rid, 300, "config data not exist");
+          resp["lastModified"] = json!(0);
+          resp["beta"] = json!(false);
+          return ("ConfigQueryResponse", resp);
+        };
+        let is_beta = conf.beta_content.is_some() && conf.beta_ips.iter().any(|ip| ip == client_ip);
+        let content = if is_beta {
+          conf.beta_content.clone().unwrap_or_default()
+        } else {
+          conf.content.clone()
+        };
+        (
+          "ConfigQueryResponse",
+          ok_response(
+            &rid,
+            json!({
+              "md5": md5_hex(&content),
+              "content": content,
+              "contentType": conf.content_type,
+              "encryptedDataKey": conf.encrypted_data_key,
+              "lastModified": 0,
+              "beta": is_beta,
+            }),
+          ),
+        )
+      }
+      "ConfigPublishRequest" => {
+        let key = config_key(&body);
+        let content = str_field(&body, "content");
+        let addition = body.get("additionMap").cloned().unwrap_or(json!({}));
+        let cas_md5 = body
+          .get("casMd5")
+          .and_then(Value::as_str)
+          .map(str::to_string);
+        if content.trim().is_empty() {
+          return (
+            "ConfigPublishResponse",
+            fail_response(&rid, 400, "content invalid"),
+          );
+        }
+        let mut state = self.state.lock().unwrap();
+        if let Some(cas_md5) = cas_md5 {
+          let current = state
+            .configs
+            .get(&key)
+            .map(|conf| conf.md5.clone())
+            .unwrap_or_default();
+          if current != cas_md5 {
+            return (
+              "ConfigPublishResponse",
+              fail_response(&rid, 500, "Cas publish fail, server md5 may have changed."),
+            );
+          }
+        }
+        let beta_ips = str_field(&addition, "betaIps");
+        let conf = state.configs.entry(key.clone()).or_default();
+        if beta_ips.is_empty() {
+          conf.md5 = md5_hex(&content);
+          conf.content = content;
+          conf.content_type = addition
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or("text")
+            .to_string();
+          conf.encrypted_data_key = str_field(&addition, "encryptedDataKey");
+        } else {
+          conf.beta_ips = beta_ips.split(',').map(str::to_string).collect();
+          conf.beta_content = Some(content);
+        }
+        notify_config(&state, &key);
+        ("ConfigPublishResponse", ok_response(&rid, json!({})))
+      }
+      "ConfigRemoveRequest" => {
+        let key = config_key(&body);
+        let mut state = self.state.lock().unwrap();
+        state.configs.remove(&key);
+        notify_config(&state, &key);
+        ("ConfigRemoveResponse", ok_response(&rid, json!({})))
+      }
+      "ConfigBatchListenRequest" => {
+        let listen = body.get("listen").and_then(Value::as_bool).unwrap_or(true);
+        let contexts = body
+          .get("configListenContexts")
+          .and_then(Value::as_array)
+          .cloned()
+          .unwrap_or_default();
+        let mut changed = Vec::new();
+        let mut state = self.state.lock().unwrap();
+        for context in contexts {
+          let key = config_key(&context);
+          if !listen {
+            if let Some(conns) = state.config_listeners.get_mut(&key) {
+              conns.remove(conn_id);
+            }
+            continue;
+          }
+          state
+            .config_listeners
+            .entry(key.clone())
+            .or_default()
+            .insert(conn_id.to_string());
+          let server_md5 = state
+            .configs
+            .get(&key)
+            .map(|conf| conf.md5.clone())
+            .unwrap_or_default();
+          if server_md5 != str_field(&context, "md5") {
+            changed.push(json!({ "dataId": key.2, "group": key.1, "tenant": key.0 }));
+          }
+        }
+        (
+          "ConfigChangeBatchListenResponse",
+          ok_response(&rid, json!({ "changedConfigs": changed })),
+        )
+      }
+      "InstanceRequest" | "PersistentInstanceRequest" | "BatchInstanceRequest" => {
+        let key = service_key(&body);
+        let r_kind = str_field(&body, "type");
+        let instances = match body.get("instances").and_then(Value::as_array) {
+          Some(instances) => instances.clone(),
+          None => body.get("instance").cloned().into_iter().collect(),
+        };
+        let mut state = self.state.lock().unwrap();
+        let hosts = state.services.entry(key.clone()).or_default();
+        for mut instance in instances {
+          if instance.get("clusterName").is_none_or(Value::is_null) {
+            instance["clusterName"] = json!("DEFAULT");
+          }
+          instance["serviceName"] = json!(format!("{}@@{}", key.1, key.2));
+          hosts.retain(|host| {
+            host["ip"] != instance["ip"]
+              || host["port"] != instance["port"]
+              || host["clusterName"] != instance["clusterName"]
+          });
+          if r_kind == "deregisterInstance" {
+            continue;
+          }
+          if instance.get("instanceId").is_none_or(Value::is_null) {
+            instance["instanceId"] = json!(format!(
+              "{}#{}#{}#{}@@{}",
+              instance["ip"].as_str().unwrap_or_default(),
+              instance["port"],
+              instance["clusterName"].as_str().unwrap_or_default(),
+              key.1,
+              key.2
+            ));
+          }
+          hosts.push(instance);
+        }
+        notify_service(&mut state, &key);
+        let resp_type = if r_type == "BatchInstanceRequest" {
+          "BatchInstanceResponse"
+        } else {
+          "InstanceResponse"
+        };
+        (resp_type, ok_response(&rid, json!({ "type": r_kind })))
+      }
+      "SubscribeServiceRequest" | "ServiceQueryRequest" => {
+        let key = service_key(&body);
+        let is_subscribe = r_type == "SubscribeServiceRequest";
+        let clusters = str_field(&body, if is_subscribe { "clusters" } else { "cluster" });
+        let mut state = self.state.lock().unwrap();
+        if is_subscribe {
+          let subscribers = state.service_subscribers.entry(key.clone()).or_default();
+          let subscriber = (conn_id.to_string(), clusters.clone());
+          if body
+            .get("subscribe")
+            .and_then(Value::as_bool)
+            .unwrap_or(true)
+          {
+            subscribers.insert(subscriber);
+          } else {
+            subscribers.remove(&subscriber);
+          }
+        }
+        let service_info = service_info(&mut state, &key, &clusters);
+        let resp_type = if is_subscribe {
+          "SubscribeServiceResponse"
+        } else {
+          "QueryServiceResponse"
+        };
+        (
+          resp_type,
+          ok_response(&rid, json!({ "serviceInfo": service_info })),
+        )
+      }
+      "ServiceListRequest" => {
+        let (namespace, group) = (str_field(&body, "namespace"), str_field(&body, "groupName"));
+        let state = self.state.lock().unwrap();
+        let names: Vec<String> = state
+          .services
+          .keys()
+          .filter(|key| key.0 == namespace && key.1 == group)
+          .map(|key| key.2.clone())
+          .collect();
+        (
+          "ServiceListResponse",
+          ok_response(&rid, json!({ "count": names.len(), "serviceNames": names })),
+        )
+      }
+      _ => (
+        "ErrorResponse",
+        fail_response(&rid, 501, "unsupported request"),
+      ),
+    }
+  }
+
+  fn unary(&self, request: tonic::Request<Payload>) -> Payload {
+    let conn_id = request
+      .remote_addr()
+      .map(|addr| addr.to_string())
+      .unwrap_or_default();
+    let payload = request.into_inner();
+    let metadata = payload.metadata.unwrap_or_default();
+    let body: Value = payload
+      .body
+      .and_then(|any| serde_json::from_slice(&any.value).ok())
+      .unwrap_or(Value::Null);
+    let (r_type, resp) = self.handle(&conn_id, &metadata.client_ip, &metadata.r#type, body);
+    to_payload(r_type, &resp)
+  }
+}
+
+fn config_key(body: &Value) -> ConfigKey {
+  (
+    str_field(body, "tenant"),
+    str_field(body, "group"),
+    str_field(body, "dataId"),
+  )
+}
+
+fn service_key(body: &Value) -> ServiceKey {
+  (
+    str_field(body, "namespace"),
+    str_field(body, "groupName"),
+    str_field(body, "serviceName"),
+  )
+}
+
+/// The instances of service in clusters, all clusters if empty.
+fn service_info(state: &mut MockState, key: &ServiceKey, clusters: &str) -> Value {
+  state.ref_time += 1;
+  let wanted: Vec<&str> = clusters
+    .split(',')
+    .filter(|cluster| !cluster.is_empty())
+    .collect();
+  let hosts: Vec<Value> = state
+    .services
+    .get(key)
+    .cloned()
+    .unwrap_or_default()
+    .into_iter()
+    .filter(|host| {
+      wanted.is_empty() || wanted.contains(&host["clusterName"].as_str().unwrap_or_default())
+    })
+    .collect();
+  json!({
+    "name": key.2,
+    "groupName": key.1,
+    "clusters": clusters,
+    "cacheMillis": 10000,
+    "lastRefTime": state.ref_time,
+    "checksum": "",
+    "allIPs": false,
+    "reachProtectionThreshold": false,
+    "hosts": hosts,
+  })
+}
+
+/// Push the request to the client by the bi stream of connection.
+fn push(state: &MockState, conn_id: &str, r_type: &str, body: Value) {
+  if let Some(sender) = state.connections.get(conn_id) {
+    let _ = sender.try_send(Ok(to_payload(r_type, &body)));
+  }
+}
+
+fn notify_config(state: &MockState, key: &ConfigKey) {
+  let conns = state.config_listeners.get(key).cloned().unwrap_or_default();
+  for conn_id in conns {
+    let body = json!({
+      "dataId": key.2,
+      "group": key.1,
+      "tenant": key.0,
+      "headers": {},
+      "requestId": "mock-push",
+    });
+    push(state, &conn_id, "ConfigChangeNotifyRequest", body);
+  }
+}
+
+fn notify_service(state: &mut MockState, key: &ServiceKey) {
+  let subscribers = state
+    .service_subscribers
+    .get(key)
+    .cloned()
+    .unwrap_or_default();
+  for (conn_id, clusters) in subscribers {
+    let body = json!({
+      "serviceInfo": service_info(state, key, &clusters),
+      "namespace": key.0,
+      "groupName": key.1,
+      "serviceName": key.2,
+      "headers": {},
+      "requestId": "mock-push",
+    });
+    push(state, &conn_id, "NotifySubscriberRequest", body);
+  }
+}
+
+/// Match the value by the pattern of blur search, `*` matches any chars, and the empty pattern matches all.
+fn blur_match(pattern: &str, value: &str) -> bool {
+  if pattern.is_empty() {
+    return true;
+  }
+  let parts: Vec<&str> = pattern.split('*').collect();
+  let (first, last) = (parts[0], parts[parts.len() - 1]);
+  if parts.len() == 1 {
+    return value == pattern;
+  }
+  if !value.starts_with(first) || !value[first.len()..].ends_with(last) {
+    return false;
+  }
+  let mut rest = &value[first.len()..value.len() - last.len()];
+  for part in &parts[1..parts.len() - 1] {
+    match rest.find(part) {
+      Some(index) => rest = &rest[index + part.len()..],
+      None => return false,
+    }
+  }
+  true
+}
+
+fn md5_hex(content: &str) -> String {
+  format!("{:x}", md5::compute(content))
+}
+
+fn url_decode(value: &str) -> String {
+  let bytes = value.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    match bytes[i] {
+      b'+' => decoded.push(b' '),
+      b'%' if i + 2 < bytes.len() => {
+        let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+        match u8::from_str_radix(hex, 16) {
+          Ok(byte) => {
+            decoded.push(byte);
+            i += 2;
+          }
+          Err(_) => decoded.push(b'%'),
+        }
+      }
+      byte => decoded.push(byte),
+    }
+    i += 1;
+  }
+  String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn str_field(body: &Value, key: &str) -> String {
+  body
+    .get(key)
+    .and_then(Value::as_str)
+    .unwrap_or_default()
+    .to_string()
+}
+
+fn ok_response(request_id: &Value, mut body: Value) -> Value {
+  body["resultCode"] = json!(200);
+  body["errorCode"] = json!(0);
+  body["message"] = json!("Response ok");
+  body["requestId"] = request_id.clone();
+  body
+}
+
+fn fail_response(request_id: &Value, error_code: i32, message: &str) -> Value {
+  json!({
+    "resultCode": 500,
+    "errorCode": error_code,
+    "message": message,
+    "requestId": request_id,
+  })
+}
+
+fn to_payload(r_type: &str, body: &Value) -> Payload {
+  Payload {
+    metadata: Some(Metadata {
+      r#type: r_type.to_string(),
+      client_ip: String::new(),
+      headers: HashMap::new(),
+    }),
+    body: Some(prost_types::Any {
+      type_url: r_type.to_string(),
+      value: serde_json::to_vec(body).unwrap_or_default(),
+    }),
+  }
+}
+
+/// The unary grpc service `Request/request` of nacos.
+#[derive(Clone)]
+struct RequestServer(MockServer);
+
+/// The bi stream grpc service `BiRequestStream/requestBiStream` of nacos, which pushes the changes.
+#[derive(Clone)]
+struct BiRequestStreamServer(MockServer);
+
+impl tonic::server::NamedService for RequestServer {
+  const NAME: &'static str = "Request";
+}
+
+impl tonic::server::NamedService for BiRequestStreamServer {
+  const NAME: &'static str = "BiRequestStream";
+}
+
+fn unimplemented_response() -> http::Response<tonic::body::BoxBody> {
+  let mut response = http::Response::new(empty_body());
+  response.headers_mut().insert(
+    "grpc-status",
+    http::HeaderValue::from(tonic::Code::Unimplemented as i32),
+  );
+  response.headers_mut().insert(
+    http::header::CONTENT_TYPE,
+    http::HeaderValue::from_static("application/grpc"),
+  );
+  response
+}
+
+impl<B> Service<http::Request<B>> for RequestServer
+where
+  B: Body + Send + 'static,
+  B::Error: Into<StdError> + Send + 'static,
+{
+  type Response = http::Response<tonic::body::BoxBody>;
+  type Error = std::convert::Infallible;
+  type Future = BoxFuture<Self::Response, Self::Error>;
+
+  fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+    Poll::Ready(Ok(()))
+  }
+
+  fn call(&mut self, req: http::Request<B>) -> Self::Future {
+    struct Unary(MockServer);
+
+    impl tonic::server::UnaryService<Payload> for Unary {
+      type Response = Payload;
+      type Future = BoxFuture<tonic::Response<Payload>, tonic::Status>;
+
+      fn call(&mut self, request: tonic::Request<Payload>) -> Self::Future {
+        let resp = self.0.unary(request);
+        Box::pin(async move { Ok(tonic::Response::new(resp)) })
+      }
+    }
+
+    let server = self.0.clone();
+    Box::pin(async move {
+      if req.uri().path() != "/Request/request" {
+        return Ok(unimplemented_response());
+      }
+      let mut grpc =
+        tonic::server::Grpc::new(tonic::codec::ProstCodec::<Payload, Payload>::default());
+      Ok(grpc.unary(Unary(server), req).await)
+    })
+  }
+}
+
+impl<B> Service<http::Request<B>> for BiRequestStreamServer
+where
+  B: Body + Send + 'static,
+  B::Error: Into<StdError> + Send + 'static,
+{
+  type Response = http::Response<tonic::body::BoxBody>;
+  type Error = std::convert::Infallible;
+  type Future = BoxFuture<Self::Response, Self::Error>;
+
+  fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+    Poll::Ready(Ok(()))
+  }
+
+  fn call(&mut self, req: http::Request<B>) -> Self::Future {
+    struct Streaming(MockServer);
+
+    impl tonic::server::StreamingService<Payload> for Streaming {
+      type Response = Payload;
+      type ResponseStream =
+        tokio_stream::wrappers::ReceiverStream<std::result::Result<Payload, tonic::Status>>;
+      type Future = BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
+
+      fn call(&mut self, request: tonic::Request<tonic::Streaming<Payload>>) -> Self::Future {
+        let conn_id = request
+          .remote_addr()
+          .map(|addr| addr.to_string())
+          .unwrap_or_default();
+        let (sender, receiver) = mpsc::channel(1024);
+        let state = self.0.state.clone();
+        state
+          .lock()
+          .unwrap()
+          .connections
+          .insert(conn_id.clone(), sender);
+        let mut inbound = request.into_inner();
+        // forget the connection once the client disconnects
+        tokio::spawn(async move {
+          while let Ok(Some(_)) = inbound.message().await {}
+          let mut state = state.lock().unwrap();
+          state.connections.remove(&conn_id);
+          for conns in state.config_listeners.values_mut() {
+            conns.remove(&conn_id);
+          }
+          for subscribers in state.service_subscribers.values_mut() {
+            subscribers.retain(|(id, _)| id != &conn_id);
+          }
+        });
+        Box::pin(async move {
+          Ok(tonic::Response::new(
+            tokio_stream::wrappers::ReceiverStream::new(receiver),
+          ))
+        })
+      }
+    }
+
+    let server = self.0.clone();
+    Box::pin(async move {
+      if req.uri().path() != "/BiRequestStream/requestBiStream" {
+        return Ok(unimplemented_response());
+      }
+      let mut grpc =
+        tonic::server::Grpc::new(tonic::codec::ProstCodec::<Payload, Payload>::default());
+      Ok(grpc.streaming(Streaming(server), req).await)
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::MockNacos;
+  use nacos_sdk::api::config::{ConfigChangeListener, ConfigResponse, ConfigServiceBuilder};
+  use nacos_sdk::api::naming::{NamingServiceBuilder, ServiceInstance};
+  use nacos_sdk::api::props::ClientProps;
+  use std::collections::HashSet;
+  use std::sync::Arc;
+  use std::time::Duration;
+  use tokio::sync::mpsc;
+
+  struct ChannelListener(mpsc::UnboundedSender<String>);
+
+  impl ConfigChangeListener for ChannelListener {
+    fn notify(&self, config_resp: ConfigResponse) {
+      let _ = self.0.send(config_resp.content().clone());
+    }
+  }
+
+  #[tokio::test]
+  async fn serve_config_and_naming() {
+    let mock = MockNacos::start().await.unwrap();
+    let props = ClientProps::new()
+      .server_addr(mock.server_addr())
+      .namespace("");
+
+    let config = ConfigServiceBuilder::new(props.clone())
+      .build()
+      .await
+      .unwrap();
+    let (data_id, group) = ("mock-test".to_string(), "TEST_GROUP".to_string());
+    assert!(
+      config
+        .publish_config(data_id.clone(), group.clone(), "v1".to_string(), None)
+        .await
+        .unwrap()
+    );
+    let config_resp = config
+      .get_config(data_id.clone(), group.clone())
+      .await
+      .unwrap();
+    assert_eq!(config_resp.content(), "v1");
+    assert!(matches!(
+      config
+        .get_config("missing".to_string(), group.clone())
+        .await,
+      Err(nacos_sdk::api::error::Error::ConfigNotFound(_))
+    ));
+
+    let (sender, mut received) = mpsc::unbounded_channel();
+    config
+      .add_listener(
+        data_id.clone(),
+        group.clone(),
+        Arc::new(ChannelListener(sender)),
+      )
+      .await
+      .unwrap();
+    // nacos-sdk sends the listen request in background, a change published before the mock has it is not notified
+    let key = (String::new(), group.clone(), data_id.clone());
+    let listened = tokio::time::timeout(Duration::from_secs(5), async {
+      while !mock
+        .state
+        .lock()
+        .unwrap()
+        .config_listeners
+        .get(&key)
+        .is_none_or(HashSet::is_empty)
+      {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+      }
+    })
+    .await;
+    assert!(listened.is_ok());
+    config
+      .publish_config(data_id, group, "v2".to_string(), None)
+      .await
+      .unwrap();
+    let notified = tokio::time::timeout(Duration::from_secs(5), received.recv()).await;
+    assert_eq!(notified.unwrap().as_deref(), Some("v2"));
+
+    let naming = NamingServiceBuilder::new(props).build().await.unwrap();
+    let instance = ServiceInstance {
+      ip: "127.0.0.1".to_string(),
+      port: 8080,
+      ..Default::default()
+    };
+    naming
+      .register_instance(
+        "mock-service".to_string(),
+        Some("TEST_GROUP".to_string()),
+        instance,
+      )
+      .await
+      .unwrap();
+    let instances = naming
+      .get_all_instances(
+        "mock-service".to_string(),
+        Some("TEST_GROUP".to_string()),
+        Vec::new(),
+        false,
+      )
+      .await
+      .unwrap();
+    assert_eq!(
+      instances
+        .iter()
+        .map(|instance| (instance.ip.as_str(), instance.port))
+        .collect::<Vec<_>>(),
+      [("127.0.0.1", 8080)]
+    );
+
+    mock.close();
+  }
+}
@@ -1,14 +1,21 @@
 #![deny(clippy::all)]
 
-use napi::{bindgen_prelude::*, threadsafe_function::*};
-use std::sync::Arc;
+use napi::{JsObject, Ref, bindgen_prelude::*, threadsafe_function::*};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 /// Client api of Nacos Naming.
-#[napi]
+#[napi(custom_finalize)]
 pub struct NacosNamingClient {
   inner: nacos_sdk::api::naming::NamingService,
+  listeners: Arc<crate::ListenerRegistry<NamingListenKey, NacosNamingEventListener>>,
+  dispatchers: Arc<Mutex<HashMap<NamingListenKey, Arc<NamingEventDispatcher>>>>,
+  subscribes: NamingSubscribeQueue,
 }
 
+/// (service_name, group, clusters sorted and joined by ',')
+type NamingListenKey = (String, String, String);
+
 #[napi]
 impl NacosNamingClient {
   /// Build a Naming Client.
@@ -62,6 +69,9 @@ impl NacosNamingClient {
 
     Ok(NacosNamingClient {
       inner: naming_service,
+      listeners: Arc::new(crate::ListenerRegistry::new()),
+      dispatchers: Arc::new(Mutex::new(HashMap::new())),
+      subscribes: NamingSubscribeQueue::default(),
     })
   }
 
@@ -211,67 +221,444 @@ impl NacosNamingClient {
   }
 
   /// Add NacosNamingEventListener callback func, which listen the instance change.
+  /// Return a Subscription, dispose it to remove the listener.
   /// If it fails, pay attention to err
-  #[napi]
-  pub async fn subscribe(
+  #[napi(ts_return_type = "Promise<Subscription>")]
+  pub fn subscribe(
     &self,
+    env: Env,
     service_name: String,
     group: String,
     clusters: Option<Vec<String>>,
-    listener: ThreadsafeFunction<Vec<NacosServiceInstance>>,
-  ) -> Result<()> {
-    self
-      .inner
-      .subscribe(
-        service_name,
-        Some(group),
-        clusters.unwrap_or_default(),
-        Arc::new(NacosNamingEventListener {
-          func: Arc::new(listener),
-        }),
-      )
-      .await
-      .map_err(|nacos_err| Error::from_reason(nacos_err.to_string()))?;
-    Ok(())
+    #[napi(ts_arg_type = "(err: Error | null, arg: Array<NacosServiceInstance>) => any")]
+    listener: JsFunction,
+  ) -> Result<JsObject> {
+    let js_func = env.create_reference(&listener)?;
+    let event_listener = Arc::new(NacosNamingEventListener {
+      sink: NamingEventSink::Callback(Arc::new(
+        listener.create_threadsafe_function(0, |ctx| Ok(vec![ctx.value]))?,
+      )),
+    });
+
+    self.subscribe_listener(env, service_name, group, clusters, js_func, event_listener)
   }
 
-  /// Remove NacosNamingEventListener callback func, but noop....
-  /// The logic is not implemented internally, and only APIs are provided as compatibility.
-  /// Users maybe do not need it? Not removing the subscription is not a big problem, Sorry!
-  #[napi]
-  pub async fn un_subscribe(
+  /// Add NacosNamingEventListener callback func, which listen the instance change as diff,
+  /// compared with the previous instances of this subscription, keyed by ip:port:cluster.
+  /// Return a Subscription, dispose it (or `unSubscribe` the func) to remove the listener.
+  /// If it fails, pay attention to err
+  #[napi(ts_return_type = "Promise<Subscription>")]
+  pub fn subscribe_diff(
     &self,
-    _service_name: String,
-    _group: String,
-    _clusters: Option<Vec<String>>,
-    _listener: ThreadsafeFunction<Vec<NacosServiceInstance>>,
-  ) -> Result<()> {
+    env: Env,
+    service_name: String,
+    group: String,
+    clusters: Option<Vec<String>>,
+    #[napi(ts_arg_type = "(err: Error | null, arg: NacosInstancesDiff) => any")]
+    listener: JsFunction,
+  ) -> Result<JsObject> {
+    let js_func = env.create_reference(&listener)?;
+    let event_listener = Arc::new(NacosNamingEventListener {
+      sink: NamingEventSink::Diff {
+        func: Arc::new(listener.create_threadsafe_function(0, |ctx| Ok(vec![ctx.value]))?),
+        previous: Mutex::new(Vec::new()),
+      },
+    });
+
+    self.subscribe_listener(env, service_name, group, clusters, js_func, event_listener)
+  }
+
+  /// Remove NacosNamingEventListener callback func, the same func which passed to `subscribe`
+  /// with the same service, group and clusters.
+  /// If the func was subscribed more than once, only one of them is removed.
+  /// If it fails, pay attention to err
+  #[napi(ts_return_type = "Promise<void>")]
+  pub fn un_subscribe(
+    &self,
+    env: Env,
+    service_name: String,
+    group: String,
+    clusters: Option<Vec<String>>,
+    #[napi(ts_arg_type = "(err: Error | null, arg: Array<NacosServiceInstance>) => any")]
+    listener: JsFunction,
+  ) -> Result<JsObject> {
+    let clusters = sorted_clusters(clusters);
+    let key = (service_name.clone(), group.clone(), clusters.join(","));
+    // Unsubscribe from nacos-sdk only when the last listener of the key removed.
+    let dispatcher = {
+      let mut dispatchers = self.dispatchers.lock().unwrap();
+      let removed = self.listeners.remove_by_func(&env, &key, &listener)?;
+      if removed.is_some() && !self.listeners.contains_key(&key) {
+        dispatchers.remove(&key)
+      } else {
+        None
+      }
+    };
+
+    let inner = self.inner.clone();
+    let unsubscribed = dispatcher.map(|dispatcher| {
+      self.subscribes.queue(&key, async move {
+        inner
+          .unsubscribe(service_name, Some(group), clusters, dispatcher)
+          .await
+      })
+    });
+    env.spawn_future(async move {
+      if let Some(unsubscribed) = unsubscribed {
+        unsubscribed
+          .await
+          .map_err(|nacos_err| Error::from_reason(nacos_err.to_string()))?;
+      }
+      Ok(())
+    })
+  }
+
+  /// Watch the instance change, return an async iterator of instances snapshot,
+  /// e.g. `for await (const instances of client.watchInstances(serviceName, group))`.
+  /// Only the newest snapshot is kept until consumed. Break out of the loop to unsubscribe.
+  /// If it fails, pay attention to err of the iterator
+  #[napi(ts_return_type = "NacosNamingWatcher")]
+  pub fn watch_instances(
+    &self,
+    env: Env,
+    service_name: String,
+    group: String,
+    clusters: Option<Vec<String>>,
+  ) -> Result<JsObject> {
+    let queue = Arc::new(crate::WatchQueue::new(Some(crate::WatchOptions {
+      capacity: Some(1),
+      overflow: Some(crate::OverflowPolicy::CoalesceLatest),
+    })));
+    let event_listener = Arc::new(NacosNamingEventListener {
+      sink: NamingEventSink::Watch(queue.clone()),
+    });
+
+    let clusters = sorted_clusters(clusters);
+    let key = (service_name.clone(), group.clone(), clusters.join(","));
+    let (dispatcher, id) = self.register_listener(key.clone(), None, event_listener);
+
+    let inner = self.inner.clone();
+    let listeners = self.listeners.clone();
+    let dispatchers = self.dispatchers.clone();
+    let watch_queue = queue.clone();
+    let watch_key = key.clone();
+    let subscribed = dispatcher.clone();
+    let subscribed = self.subscribes.queue(&key, async move {
+      inner
+        .subscribe(service_name, Some(group), clusters, subscribed)
+        .await
+    });
+    spawn(async move {
+      if let Err(nacos_err) = subscribed.await {
+        let mut dispatchers = dispatchers.lock().unwrap();
+        listeners.remove_unbound(&watch_key, id);
+        forget_dispatcher(&mut dispatchers, &listeners, &watch_key, &dispatcher);
+        drop(dispatchers);
+        watch_queue.fail(nacos_err.to_string());
+      }
+    });
+
+    let watcher = NacosNamingWatcher {
+      queue,
+      target: NamingSubscriptionTarget {
+        inner: self.inner.clone(),
+        listeners: self.listeners.clone(),
+        dispatchers: self.dispatchers.clone(),
+        subscribes: self.subscribes.clone(),
+        key,
+        id,
+      },
+    };
+    let mut object = watcher.into_instance(env)?.as_object(env);
+    crate::set_async_iterator(&env, &mut object)?;
+    Ok(object)
+  }
+}
+
+impl ObjectFinalize for NacosNamingClient {
+  fn finalize(self, env: Env) -> Result<()> {
+    self.listeners.release_js_funcs(env)
+  }
+}
+
+impl NacosNamingClient {
+  /// Subscribe the listener bind with js callback to nacos-sdk, resolve a Subscription.
+  fn subscribe_listener(
+    &self,
+    env: Env,
+    service_name: String,
+    group: String,
+    clusters: Option<Vec<String>>,
+    js_func: Ref<()>,
+    event_listener: Arc<NacosNamingEventListener>,
+  ) -> Result<JsObject> {
+    let clusters = sorted_clusters(clusters);
+    let key = (service_name.clone(), group.clone(), clusters.join(","));
+    let (dispatcher, id) = self.register_listener(key.clone(), Some(js_func), event_listener);
+
+    let inner = self.inner.clone();
+    let subscribed = dispatcher.clone();
+    let subscribed = self.subscribes.queue(&key, async move {
+      inner
+        .subscribe(service_name, Some(group), clusters, subscribed)
+        .await
+    });
+    let target = NamingSubscriptionTarget {
+      inner: self.inner.clone(),
+      listeners: self.listeners.clone(),
+      dispatchers: self.dispatchers.clone(),
+      subscribes: self.subscribes.clone(),
+      key,
+      id,
+    };
+    env.execute_tokio_future(
+      async move { Ok(subscribed.await) },
+      move |env, ret| match ret {
+        Ok(()) => crate::Subscription::new(target).into_js_object(env),
+        Err(nacos_err) => {
+          let mut dispatchers = target.dispatchers.lock().unwrap();
+          target.listeners.remove_by_id(env, &target.key, id)?;
+          forget_dispatcher(
+            &mut dispatchers,
+            &target.listeners,
+            &target.key,
+            &dispatcher,
+          );
+          Err(Error::from_reason(nacos_err.to_string()))
+        }
+      },
+    )
+  }
+
+  /// Register the listener with the dispatcher of key, return the dispatcher for subscribing to nacos-sdk.
+  /// Only one dispatcher for the same key subscribe to nacos-sdk, it dispatch events to all listeners.
+  fn register_listener(
+    &self,
+    key: NamingListenKey,
+    js_func: Option<Ref<()>>,
+    event_listener: Arc<NacosNamingEventListener>,
+  ) -> (Arc<NamingEventDispatcher>, u64) {
+    let mut dispatchers = self.dispatchers.lock().unwrap();
+    let dispatcher = dispatchers
+      .entry(key.clone())
+      .or_insert_with(|| {
+        Arc::new(NamingEventDispatcher {
+          key: key.clone(),
+          listeners: self.listeners.clone(),
+        })
+      })
+      .clone();
+    let id = self.listeners.insert(key, js_func, event_listener);
+    (dispatcher, id)
+  }
+}
+
+/// Clusters sorted and deduped, so that the listen key of the same clusters does not depend on their order.
+fn sorted_clusters(clusters: Option<Vec<String>>) -> Vec<String> {
+  let mut clusters = clusters.unwrap_or_default();
+  clusters.sort();
+  clusters.dedup();
+  clusters
+}
+
+/// Forget the dispatcher which failed to subscribe to nacos-sdk, once no listener of the key is left,
+/// so that the next subscribe of the key creates and subscribes a new one instead of reusing it.
+fn forget_dispatcher(
+  dispatchers: &mut HashMap<NamingListenKey, Arc<NamingEventDispatcher>>,
+  listeners: &crate::ListenerRegistry<NamingListenKey, NacosNamingEventListener>,
+  key: &NamingListenKey,
+  dispatcher: &Arc<NamingEventDispatcher>,
+) {
+  if !listeners.contains_key(key)
+    && dispatchers
+      .get(key)
+      .is_some_and(|current| Arc::ptr_eq(current, dispatcher))
+  {
+    dispatchers.remove(key);
+  }
+}
+
+/// Async iterator of the instances snapshot, returned by `watchInstances`.
+#[napi]
+pub struct NacosNamingWatcher {
+  queue: Arc<crate::WatchQueue<Vec<NacosServiceInstance>>>,
+  target: NamingSubscriptionTarget,
+}
+
+#[napi]
+impl NacosNamingWatcher {
+  /// Wait for the next instances snapshot, done after the watcher closed.
+  /// If it fails, pay attention to err
+  #[napi]
+  pub async fn next(&self) -> Result<NacosNamingWatchResult> {
+    let value = self.queue.recv().await?;
+    Ok(NacosNamingWatchResult {
+      done: value.is_none(),
+      value,
+    })
+  }
+
+  /// Close the watcher and unsubscribe, `break` of `for await` calls it.
+  #[napi(js_name = "return")]
+  pub fn close(&self, env: Env) -> Result<NacosNamingWatchResult> {
+    self.queue.close();
+    crate::SubscriptionTarget::dispose(&self.target, &env)?;
+    Ok(NacosNamingWatchResult {
+      done: true,
+      value: None,
+    })
+  }
+}
+
+#[napi(object)]
+pub struct NacosNamingWatchResult {
+  /// Whether the watcher closed
+  pub done: bool,
+  /// The instances snapshot, absent if done
+  pub value: Option<Vec<NacosServiceInstance>>,
+}
+
+/// Remove the listener of [`crate::Subscription`] from [`NacosNamingClient`].
+struct NamingSubscriptionTarget {
+  inner: nacos_sdk::api::naming::NamingService,
+  listeners: Arc<crate::ListenerRegistry<NamingListenKey, NacosNamingEventListener>>,
+  dispatchers: Arc<Mutex<HashMap<NamingListenKey, Arc<NamingEventDispatcher>>>>,
+  subscribes: NamingSubscribeQueue,
+  key: NamingListenKey,
+  id: u64,
+}
+
+impl crate::SubscriptionTarget for NamingSubscriptionTarget {
+  fn is_active(&self) -> bool {
+    self.listeners.contains_id(&self.key, self.id)
+  }
+
+  fn dispose(&self, env: &Env) -> Result<()> {
+    // Unsubscribe from nacos-sdk only when the last listener of the key removed.
+    let dispatcher = {
+      let mut dispatchers = self.dispatchers.lock().unwrap();
+      let removed = self.listeners.remove_by_id(env, &self.key, self.id)?;
+      if removed.is_some() && !self.listeners.contains_key(&self.key) {
+        dispatchers.remove(&self.key)
+      } else {
+        None
+      }
+    };
+
+    if let Some(dispatcher) = dispatcher {
+      let inner = self.inner.clone();
+      let (service_name, group, clusters) = self.key.clone();
+      let clusters = clusters
+        .split(',')
+        .filter(|c| !c.is_empty())
+        .map(String::from)
+        .collect();
+      let unsubscribed = self.subscribes.queue(&self.key, async move {
+        inner
+          .unsubscribe(service_name, Some(group), clusters, dispatcher)
+          .await
+      });
+      spawn(async move {
+        let _ = unsubscribed.await;
+      });
+    }
     Ok(())
   }
 }
 
+/// The subscribes and unsubscribes of each key to nacos-sdk, run one after another in the order they are queued,
+/// since nacos-sdk unsubscribes the key from server whichever listener is unsubscribed,
+/// e.g. the unsubscribe of a watcher closed at once must not run before its subscribe, nor after the next one.
+#[derive(Clone, Default)]
+struct NamingSubscribeQueue(
+  Arc<Mutex<HashMap<NamingListenKey, tokio::sync::oneshot::Receiver<()>>>>,
+);
+
+impl NamingSubscribeQueue {
+  /// Queue the subscribe or unsubscribe of key, the future returned runs it after the ones queued before.
+  fn queue<F: Future>(
+    &self,
+    key: &NamingListenKey,
+    run: F,
+  ) -> impl Future<Output = F::Output> + use<F> {
+    let (done, next) = tokio::sync::oneshot::channel::<()>();
+    let previous = self.0.lock().unwrap().insert(key.clone(), next);
+    async move {
+      if let Some(previous) = previous {
+        // closed once the previous one runs, or is dropped without running
+        let _ = previous.await;
+      }
+      let output = run.await;
+      drop(done);
+      output
+    }
+  }
+}
+
+/// Subscribe to nacos-sdk for a key, and dispatch the instance change to every [`NacosNamingEventListener`] of it.
+struct NamingEventDispatcher {
+  key: NamingListenKey,
+  listeners: Arc<crate::ListenerRegistry<NamingListenKey, NacosNamingEventListener>>,
+}
+
+impl nacos_sdk::api::naming::NamingEventListener for NamingEventDispatcher {
+  fn event(&self, event: Arc<nacos_sdk::api::naming::NamingChangeEvent>) {
+    for listener in self.listeners.get(&self.key) {
+      listener.event(event.clone());
+    }
+  }
+}
+
 pub struct NacosNamingEventListener {
-  func: Arc<ThreadsafeFunction<Vec<NacosServiceInstance>>>,
+  sink: NamingEventSink,
+}
+
+/// Where the instance change goes.
+enum NamingEventSink {
+  /// Call the js callback func.
+  Callback(Arc<ThreadsafeFunction<Vec<NacosServiceInstance>>>),
+  /// Push to the queue of a [`NacosNamingWatcher`].
+  Watch(Arc<crate::WatchQueue<Vec<NacosServiceInstance>>>),
+  /// Call the js callback func with the diff against previous instances.
+  Diff {
+    func: Arc<ThreadsafeFunction<NacosInstancesDiff>>,
+    previous: Mutex<Vec<nacos_sdk::api::naming::ServiceInstance>>,
+  },
 }
 
 impl nacos_sdk::api::naming::NamingEventListener for NacosNamingEventListener {
   fn event(&self, event: Arc<nacos_sdk::api::naming::NamingChangeEvent>) {
-    let listen = self.func.clone();
-
     if event.instances.is_none() {
       return;
     }
 
     let rust_instances = event.instances.clone().unwrap();
 
-    let js_instances = rust_instances
-      .iter()
-      .map(transfer_rust_instance_to_js)
-      .collect();
-
-    std::thread::spawn(move || {
-      listen.call(Ok(js_instances), ThreadsafeFunctionCallMode::NonBlocking);
-    });
+    match &self.sink {
+      NamingEventSink::Callback(func) => {
+        let listen = func.clone();
+        let js_instances = rust_instances
+          .iter()
+          .map(transfer_rust_instance_to_js)
+          .collect();
+        std::thread::spawn(move || {
+          listen.call(Ok(js_instances), ThreadsafeFunctionCallMode::NonBlocking);
+        });
+      }
+      NamingEventSink::Watch(queue) => queue.push(
+        rust_instances
+          .iter()
+          .map(transfer_rust_instance_to_js)
+          .collect(),
+      ),
+      NamingEventSink::Diff { func, previous } => {
+        // Queued to js under the lock, so the diffs arrive in the order they are computed.
+        let mut previous = previous.lock().unwrap();
+        let diff = diff_instances(&previous, &rust_instances);
+        *previous = rust_instances;
+        func.call(Ok(diff), ThreadsafeFunctionCallMode::NonBlocking);
+      }
+    }
   }
 }
 
@@ -299,6 +686,71 @@ pub struct NacosServiceInstance {
   pub metadata: Option<std::collections::HashMap<String, String>>,
 }
 
+#[napi(object)]
+pub struct NacosInstancesDiff {
+  /// Instances which not in previous
+  pub added: Vec<NacosServiceInstance>,
+  /// Instances which not in current
+  pub removed: Vec<NacosServiceInstance>,
+  /// Instances whose weight, healthy, enabled or metadata changed
+  pub modified: Vec<NacosServiceInstance>,
+  /// All current instances
+  pub current: Vec<NacosServiceInstance>,
+}
+
+/// Key of instance in diff, ip:port:cluster
+fn instance_diff_key(instance: &nacos_sdk::api::naming::ServiceInstance) -> String {
+  format!(
+    "{}:{}:{}",
+    instance.ip,
+    instance.port,
+    instance.cluster_name.as_deref().unwrap_or_default()
+  )
+}
+
+fn diff_instances(
+  previous: &[nacos_sdk::api::naming::ServiceInstance],
+  current: &[nacos_sdk::api::naming::ServiceInstance],
+) -> NacosInstancesDiff {
+  let previous_map: HashMap<String, &nacos_sdk::api::naming::ServiceInstance> = previous
+    .iter()
+    .map(|instance| (instance_diff_key(instance), instance))
+    .collect();
+  let current_map: HashMap<String, &nacos_sdk::api::naming::ServiceInstance> = current
+    .iter()
+    .map(|instance| (instance_diff_key(instance), instance))
+    .collect();
+
+  let mut added = Vec::new();
+  let mut modified = Vec::new();
+  for instance in current {
+    match previous_map.get(&instance_diff_key(instance)) {
+      None => added.push(transfer_rust_instance_to_js(instance)),
+      Some(old) => {
+        if old.weight != instance.weight
+          || old.healthy != instance.healthy
+          || old.enabled != instance.enabled
+          || old.metadata != instance.metadata
+        {
+          modified.push(transfer_rust_instance_to_js(instance));
+        }
+      }
+    }
+  }
+  let removed = previous
+    .iter()
+    .filter(|instance| !current_map.contains_key(&instance_diff_key(instance)))
+    .map(transfer_rust_instance_to_js)
+    .collect();
+
+  NacosInstancesDiff {
+    added,
+    removed,
+    modified,
+    current: current.iter().map(transfer_rust_instance_to_js).collect(),
+  }
+}
+
 fn transfer_js_instance_to_rust(
   js_instance: &NacosServiceInstance,
 ) -> nacos_sdk::api::naming::ServiceInstance {
@@ -0,0 +1,272 @@
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// Context path of the nacos server.
+const CONTEXT_PATH: &str = "/nacos";
+/// Default http port of the nacos server, if the server addr has no port.
+const DEFAULT_HTTP_PORT: u16 = 8848;
+/// Header of the config got, "true" if it is the beta one for this client.
+const IS_BETA_HEADER: &str = "isBeta";
+/// Max configs of a page listed, the same as nacos server.
+const LIST_PAGE_SIZE: u32 = 500;
+
+/// Client of the nacos http open api, for what nacos-sdk does not support by grpc, e.g. beta config and listing configs.
+pub(crate) struct OpenApiClient {
+  http: reqwest::Client,
+  base_urls: Vec<String>,
+  namespace: String,
+  credentials: Option<(String, String)>,
+  access_token: tokio::sync::Mutex<Option<(String, Instant)>>,
+}
+
+impl OpenApiClient {
+  pub(crate) fn new(
+    server_addr: &str,
+    namespace: String,
+    credentials: Option<(String, String)>,
+  ) -> Self {
+    let base_urls = server_addr
+      .split(',')
+      .map(str::trim)
+      .filter(|addr| !addr.is_empty())
+      .map(|addr| {
+        // keep the scheme of server addr, so that the credentials never go over plaintext for https
+        let (scheme, addr) = match addr.strip_prefix("https://") {
+          Some(addr) => ("https", addr),
+          None => ("http", addr.trim_start_matches("http://")),
+        };
+        let addr = addr.trim_end_matches('/');
+        if addr.contains(':') {
+          format!("{scheme}://{addr}{CONTEXT_PATH}")
+        } else {
+          format!("{scheme}://{addr}:{DEFAULT_HTTP_PORT}{CONTEXT_PATH}")
+        }
+      })
+      .collect();
+    OpenApiClient {
+      http: reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(1))
+        .timeout(Duration::from_secs(3))
+        .build()
+        .unwrap_or_default(),
+      base_urls,
+      namespace,
+      credentials,
+      access_token: tokio::sync::Mutex::new(None),
+    }
+  }
+
+  /// Whether this client gets the beta config of `md5`, by the `isBeta` header which nacos server sets
+  /// if the ip of this http request is one of the betaIps. Fail if the config got is not the one of `md5`,
+  /// e.g. it changed meanwhile, so the header does not tell about it.
+  pub(crate) async fn query_is_beta(
+    &self,
+    data_id: &str,
+    group: &str,
+    md5: &str,
+  ) -> Result<bool, String> {
+    let query = vec![
+      ("dataId", data_id.to_string()),
+      ("group", group.to_string()),
+      ("tenant", self.namespace.clone()),
+    ];
+    let resp = self
+      .send_raw(reqwest::Method::GET, "/v1/cs/configs", query)
+      .await?;
+    let is_beta = resp
+      .headers()
+      .get(IS_BETA_HEADER)
+      .is_some_and(|is_beta| is_beta.as_bytes().eq_ignore_ascii_case(b"true"));
+    let content = resp.text().await.map_err(|err| err.to_string())?;
+    if format!("{:x}", md5::compute(&content)) != md5 {
+      return Err(format!(
+        "config changed while checking beta, dataId={data_id}, group={group}"
+      ));
+    }
+    Ok(is_beta)
+  }
+
+  /// Stop the beta config, so that all clients get the stable one.
+  pub(crate) async fn stop_beta(&self, data_id: &str, group: &str) -> Result<bool, String> {
+    let data = self
+      .request(reqwest::Method::DELETE, "/v1/cs/configs", data_id, group)
+      .await?;
+    Ok(data.as_bool().unwrap_or(true))
+  }
+
+  /// List the configs of namespace, with the content as stored, e.g. encrypted. All pages are requested.
+  pub(crate) async fn list_configs(&self, namespace: &str) -> Result<Vec<Value>, String> {
+    let mut configs = Vec::new();
+    let mut page_no = 1;
+    loop {
+      let query = vec![
+        ("search", "blur".to_string()),
+        ("dataId", String::new()),
+        ("group", String::new()),
+        ("tenant", namespace.to_string()),
+        ("pageNo", page_no.to_string()),
+        ("pageSize", LIST_PAGE_SIZE.to_string()),
+      ];
+      let page = self
+        .send(reqwest::Method::GET, "/v1/cs/configs", query)
+        .await?;
+      let Some(items) = page.get("pageItems").and_then(Value::as_array) else {
+        return Err(format!("nacos open api list configs failed, body={page}"));
+      };
+      configs.extend(items.iter().cloned());
+      let pages = page
+        .get("pagesAvailable")
+        .and_then(Value::as_u64)
+        .unwrap_or_default();
+      if items.is_empty() || page_no >= pages {
+        return Ok(configs);
+      }
+      page_no += 1;
+    }
+  }
+
+  /// Request the beta api, return `data` of the RestResult.
+  async fn request(
+    &self,
+    method: reqwest::Method,
+    path: &str,
+    data_id: &str,
+    group: &str,
+  ) -> Result<Value, String> {
+    let query = vec![
+      ("beta", "true".to_string()),
+      ("dataId", data_id.to_string()),
+      ("group", group.to_string()),
+      ("tenant", self.namespace.clone()),
+    ];
+    let body = self.send(method, path, query).await?;
+    let code = body.get("code").and_then(Value::as_i64).unwrap_or_default();
+    if code != 200 {
+      return Err(format!("nacos open api {path} failed, body={body}"));
+    }
+    Ok(body.get("data").cloned().unwrap_or(Value::Null))
+  }
+
+  /// Request the api on each server until one responds, return the body of json.
+  async fn send(
+    &self,
+    method: reqwest::Method,
+    path: &str,
+    query: Vec<(&str, String)>,
+  ) -> Result<Value, String> {
+    let resp = self.send_raw(method, path, query).await?;
+    resp.json().await.map_err(|err| err.to_string())
+  }
+
+  /// Request the api on each server until one responds, return the response of success status.
+  async fn send_raw(
+    &self,
+    method: reqwest::Method,
+    path: &str,
+    query: Vec<(&str, String)>,
+  ) -> Result<reqwest::Response, String> {
+    let mut last_err = "no server addr".to_string();
+    for base_url in self.base_urls.iter() {
+      let mut query = query.clone();
+      match self.access_token(base_url).await {
+        Ok(Some(token)) => query.push(("accessToken", token)),
+        Ok(None) => {}
+        Err(err) => {
+          last_err = err;
+          continue;
+        }
+      }
+
+      let resp = self
+        .http
+        .request(method.clone(), format!("{base_url}{path}"))
+        .query(&query)
+        .send()
+        .await;
+      let resp = match resp {
+        Ok(resp) => resp,
+        Err(err) => {
+          last_err = err.to_string();
+          continue;
+        }
+      };
+      let status = resp.status();
+      if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!(
+          "nacos open api {path} failed, status={status}, body={body}"
+        ));
+      }
+      return Ok(resp);
+    }
+    Err(last_err)
+  }
+
+  /// Login with username & password if any, the token is cached until it expires.
+  async fn access_token(&self, base_url: &str) -> Result<Option<String>, String> {
+    let Some((username, password)) = self.credentials.as_ref() else {
+      return Ok(None);
+    };
+    let mut access_token = self.access_token.lock().await;
+    if let Some((token, expires_at)) = access_token.as_ref()
+      && Instant::now() < *expires_at
+    {
+      return Ok(Some(token.clone()));
+    }
+
+    let body: Value = self
+      .http
+      .post(format!("{base_url}/v1/auth/login"))
+      .form(&[("username", username), ("password", password)])
+      .send()
+      .await
+      .map_err(|err| err.to_string())?
+      .json()
+      .await
+      .map_err(|err| err.to_string())?;
+    let Some(token) = body.get("accessToken").and_then(Value::as_str) else {
+      return Err(format!("nacos open api login failed, body={body}"));
+    };
+    // refresh the token before it expires
+    let ttl = body
+      .get("tokenTtl")
+      .and_then(Value::as_u64)
+      .unwrap_or(18000);
+    let expires_at = Instant::now() + Duration::from_secs(ttl * 4 / 5);
+    *access_token = Some((token.to_string(), expires_at));
+    Ok(Some(token.to_string()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::OpenApiClient;
+  use std::io::Read;
+  use std::net::TcpListener;
+  use std::sync::mpsc;
+  use std::time::Duration;
+
+  /// Content type of the TLS record which starts a handshake, i.e. the ClientHello.
+  const TLS_HANDSHAKE: u8 = 0x16;
+
+  #[test]
+  fn speak_tls_to_https_server_addr() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (sender, received) = mpsc::channel();
+    std::thread::spawn(move || {
+      let (mut stream, _) = listener.accept().unwrap();
+      let mut first = [0u8; 1];
+      stream.read_exact(&mut first).unwrap();
+      let _ = sender.send(first[0]);
+    });
+
+    let client = OpenApiClient::new(&format!("https://127.0.0.1:{port}"), String::new(), None);
+    // the server never answers the handshake, only the bytes it received matter
+    let _ = crate::get_runtime().block_on(client.query_is_beta("data-id", "group", ""));
+    assert_eq!(
+      received.recv_timeout(Duration::from_secs(5)),
+      Ok(TLS_HANDSHAKE)
+    );
+  }
+}
@@ -0,0 +1,318 @@
+use nacos_sdk::api::config::ConfigChangeListener;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, Weak};
+
+type ConfigListeners = crate::ListenerRegistry<(String, String), crate::NacosConfigChangeListener>;
+
+/// Prefix of placeholder which refers to the env var, e.g. `${env:REGION}`.
+const ENV_PREFIX: &str = "env:";
+/// Max placeholders nested in the values of placeholders, e.g. `a=${b}` and `b=${c}` are 2.
+const MAX_DEPTH: usize = 32;
+/// Max length of the expanded content, and the value of each placeholder, in bytes.
+const MAX_EXPANDED_LEN: usize = 4 * 1024 * 1024;
+
+#[napi(object)]
+pub struct PlaceholderOptions {
+  /// Configs whose keys can be referred, e.g. `${db.host}`, nested keys are flatten like `a.b[0].c`.
+  /// The later config overrides the former
+  pub sources: Option<Vec<crate::NacosConfigKey>>,
+  /// Variables can be referred, prior to the keys of sources
+  pub variables: Option<HashMap<String, String>>,
+  /// Whether `${env:NAME}` refers to the env var NAME, default true
+  pub env: Option<bool>,
+}
+
+/// Why the placeholders of config can not be expanded.
+pub(crate) enum PlaceholderError {
+  /// A placeholder refers to itself, e.g. `a=${b}` and `b=${a}`, the chain is `[a, b, a]`.
+  Cycle { chain: Vec<String> },
+  /// The placeholders are nested deeper than `MAX_DEPTH`, the chain is the nested ones.
+  TooDeep { chain: Vec<String> },
+  /// The expanded content is longer than `MAX_EXPANDED_LEN`, e.g. `a=${b}${b}` and `b=${c}${c}` grow exponentially.
+  TooLong { name: Option<String> },
+}
+
+impl PlaceholderError {
+  /// Code of the js Error.
+  pub(crate) fn code(&self) -> &'static str {
+    match self {
+      PlaceholderError::Cycle { .. } => "ConfigPlaceholderCycle",
+      PlaceholderError::TooDeep { .. } | PlaceholderError::TooLong { .. } => {
+        "ConfigPlaceholderLimit"
+      }
+    }
+  }
+}
+
+impl std::fmt::Display for PlaceholderError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      PlaceholderError::Cycle { chain } => write!(f, "{}", chain.join(" -> ")),
+      PlaceholderError::TooDeep { chain } => {
+        write!(f, "nested deeper than {MAX_DEPTH}: {}", chain.join(" -> "))
+      }
+      PlaceholderError::TooLong { name: Some(name) } => {
+        write!(f, "expanded longer than {MAX_EXPANDED_LEN} bytes: {name}")
+      }
+      PlaceholderError::TooLong { name: None } => {
+        write!(f, "expanded longer than {MAX_EXPANDED_LEN} bytes")
+      }
+    }
+  }
+}
+
+/// Expand placeholders `${name}` in the config content, by variables, env vars and keys of the source configs.
+/// The placeholder which can not be resolved is kept as it is.
+pub(crate) struct PlaceholderResolver {
+  inner: crate::ConfigBackend,
+  listeners: Weak<ConfigListeners>,
+  sources: Vec<(String, String)>,
+  variables: HashMap<String, String>,
+  env: bool,
+  /// Flatten keys of the source configs, loaded and listened on first use.
+  source_values: tokio::sync::OnceCell<Vec<Mutex<BTreeMap<String, String>>>>,
+  /// Configs which refer to the sources, with the content last notified to their listeners.
+  dependents: Mutex<HashMap<(String, String), Option<String>>>,
+}
+
+impl PlaceholderResolver {
+  pub(crate) fn new(
+    inner: crate::ConfigBackend,
+    listeners: &Arc<ConfigListeners>,
+    options: PlaceholderOptions,
+  ) -> Self {
+    PlaceholderResolver {
+      inner,
+      listeners: Arc::downgrade(listeners),
+      sources: options
+        .sources
+        .unwrap_or_default()
+        .into_iter()
+        .map(|source| (source.data_id, source.group))
+        .collect(),
+      variables: options.variables.unwrap_or_default(),
+      env: options.env.unwrap_or(true),
+      source_values: tokio::sync::OnceCell::new(),
+      dependents: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Expand the content of config, record it as a dependent if it refers to the sources.
+  pub(crate) async fn expand(
+    self: &Arc<Self>,
+    data_id: &str,
+    group: &str,
+    content: &str,
+  ) -> std::result::Result<String, PlaceholderError> {
+    if !content.contains("${") {
+      return Ok(content.to_string());
+    }
+    let source_values = self.load_sources().await;
+    let sources: Vec<_> = source_values
+      .iter()
+      .map(|values| values.lock().unwrap().clone())
+      .collect();
+
+    let mut expansion = Expansion {
+      sources: &sources,
+      stack: Vec::new(),
+      expanded: HashMap::new(),
+      refers_sources: false,
+    };
+    let expanded = self.expand_str(content, &mut expansion)?;
+    if expansion.refers_sources {
+      self
+        .dependents
+        .lock()
+        .unwrap()
+        .entry((data_id.to_string(), group.to_string()))
+        .or_default();
+    }
+    Ok(expanded)
+  }
+
+  /// The expanded content has been notified to the listeners of config.
+  pub(crate) fn notified(&self, data_id: &str, group: &str, content: &str) {
+    let mut dependents = self.dependents.lock().unwrap();
+    if let Some(notified) = dependents.get_mut(&(data_id.to_string(), group.to_string())) {
+      *notified = Some(content.to_string());
+    }
+  }
+
+  fn expand_str(
+    &self,
+    content: &str,
+    expansion: &mut Expansion,
+  ) -> std::result::Result<String, PlaceholderError> {
+    let mut expanded = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(start) = rest.find("${") {
+      let Some(len) = rest[start + 2..].find('}') else {
+        break;
+      };
+      let name = &rest[start + 2..start + 2 + len];
+      expanded.push_str(&rest[..start]);
+      rest = &rest[start + 3 + len..];
+
+      if expansion.stack.iter().any(|n| n == name) {
+        let mut chain = expansion.stack.clone();
+        chain.push(name.to_string());
+        return Err(PlaceholderError::Cycle { chain });
+      }
+      if let Some(var) = name.strip_prefix(ENV_PREFIX).filter(|_| self.env) {
+        match std::env::var(var) {
+          Ok(value) => expanded.push_str(&value),
+          Err(_) => expanded.push_str(&format!("${{{name}}}")),
+        }
+      } else if let Some(value) = expansion.expanded.get(name) {
+        expanded.push_str(value);
+      } else {
+        let value = match self.variables.get(name) {
+          Some(value) => Some(value),
+          None => {
+            let value = expansion
+              .sources
+              .iter()
+              .rev()
+              .find_map(|values| values.get(name));
+            expansion.refers_sources |= value.is_some();
+            value
+          }
+        };
+        match value {
+          Some(value) => {
+            expansion.stack.push(name.to_string());
+            if expansion.stack.len() > MAX_DEPTH {
+              return Err(PlaceholderError::TooDeep {
+                chain: std::mem::take(&mut expansion.stack),
+              });
+            }
+            let value = self.expand_str(value, expansion)?;
+            expansion.stack.pop();
+            expanded.push_str(&value);
+            expansion.expanded.insert(name.to_string(), value);
+          }
+          None => expanded.push_str(&format!("${{{name}}}")),
+        }
+      }
+      expansion.check_len(&expanded)?;
+    }
+    expanded.push_str(rest);
+    expansion.check_len(&expanded)?;
+    Ok(expanded)
+  }
+
+  /// Get the source configs and listen to them, only once.
+  async fn load_sources(self: &Arc<Self>) -> &Vec<Mutex<BTreeMap<String, String>>> {
+    self
+      .source_values
+      .get_or_init(|| async {
+        let mut source_values = Vec::with_capacity(self.sources.len());
+        for (index, (data_id, group)) in self.sources.iter().enumerate() {
+          let get =
+            crate::catch_filter_error(self.inner.get_config(data_id.clone(), group.clone()));
+          let values = match get.await {
+            (Ok(config_resp), None) => flatten_config(&config_resp).unwrap_or_default(),
+            _ => BTreeMap::new(),
+          };
+          source_values.push(Mutex::new(values));
+          let _ = self
+            .inner
+            .add_listener(
+              data_id.clone(),
+              group.clone(),
+              Arc::new(SourceListener {
+                resolver: Arc::downgrade(self),
+                index,
+              }),
+            )
+            .await;
+        }
+        source_values
+      })
+      .await
+  }
+
+  /// A source config changed, notify the listeners of dependents again if their expanded content changed.
+  async fn source_changed(self: Arc<Self>) {
+    let dependents: Vec<_> = self.dependents.lock().unwrap().keys().cloned().collect();
+    for key in dependents {
+      let Some(listeners) = self.listeners.upgrade() else {
+        return;
+      };
+      let config_listeners = listeners.get(&key);
+      if config_listeners.is_empty() {
+        continue;
+      }
+      let Ok(config_resp) = self.inner.get_config(key.0.clone(), key.1.clone()).await else {
+        continue;
+      };
+      let Ok(expanded) = self.expand(&key.0, &key.1, config_resp.content()).await else {
+        continue;
+      };
+      let notified = self.dependents.lock().unwrap().get(&key).cloned().flatten();
+      if notified.as_ref() == Some(&expanded) {
+        continue;
+      }
+      for config_listener in config_listeners {
+        config_listener.notify(config_resp.clone());
+      }
+    }
+  }
+}
+
+/// State of expanding the placeholders of one config.
+struct Expansion<'a> {
+  sources: &'a [BTreeMap<String, String>],
+  /// Placeholders being expanded, the outer first.
+  stack: Vec<String>,
+  /// Placeholders expanded, so that each one is expanded only once.
+  expanded: HashMap<String, String>,
+  refers_sources: bool,
+}
+
+impl Expansion<'_> {
+  /// Fail as soon as the content being expanded is too long, before it grows any more.
+  fn check_len(&self, expanded: &str) -> std::result::Result<(), PlaceholderError> {
+    if expanded.len() > MAX_EXPANDED_LEN {
+      return Err(PlaceholderError::TooLong {
+        name: self.stack.last().cloned(),
+      });
+    }
+    Ok(())
+  }
+}
+
+/// Flatten the keys of config parsed by its format, None if it can not be parsed.
+fn flatten_config(
+  config_resp: &nacos_sdk::api::config::ConfigResponse,
+) -> Option<BTreeMap<String, String>> {
+  if config_resp.content().is_empty() {
+    return Some(BTreeMap::new());
+  }
+  let format = crate::config_format(config_resp.content_type(), config_resp.data_id());
+  let value = crate::parse_structured(&format, config_resp.content())?.ok()?;
+  Some(crate::flatten(&value))
+}
+
+/// Listen to a source config of [`PlaceholderResolver`].
+struct SourceListener {
+  resolver: Weak<PlaceholderResolver>,
+  index: usize,
+}
+
+impl ConfigChangeListener for SourceListener {
+  fn notify(&self, config_resp: nacos_sdk::api::config::ConfigResponse) {
+    let Some(resolver) = self.resolver.upgrade() else {
+      return;
+    };
+    let Some(source_values) = resolver.source_values.get() else {
+      return;
+    };
+    // keep the last values if it can not be parsed
+    if let Some(values) = flatten_config(&config_resp) {
+      *source_values[self.index].lock().unwrap() = values;
+    }
+    napi::bindgen_prelude::spawn(resolver.source_changed());
+  }
+}
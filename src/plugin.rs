@@ -1,10 +1,194 @@
-use napi::threadsafe_function::*;
-use std::sync::Arc;
+use napi::{JsFunction, JsObject, NapiRaw, bindgen_prelude::*, threadsafe_function::*};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// [`config_filter`] It is an advanced feature that does not need to be used by default;
 /// For example: 1. Encrypt ConfigReq.content value and then request; 2. Decrypt ConfigResp.content to get the value.
 pub struct NacosConfigFilter {
-  pub(crate) func: Arc<ThreadsafeFunction<(Option<NacosConfigReq>, Option<NacosConfigResp>)>>,
+  func: ConfigFilterFunc,
+  matcher: ConfigFilterMatcher,
+  timeout: Duration,
+  failures: Arc<FilterFailures>,
+}
+
+/// The js func of config filter.
+enum ConfigFilterFunc {
+  /// `(err, req, resp) => [req, resp]`, called for both request and response.
+  Tuple(ThreadsafeFunction<(Option<NacosConfigReq>, Option<NacosConfigResp>)>),
+  /// `onRequest(req) => req` and `onResponse(resp) => resp`, the absent one is not called.
+  Hooks {
+    on_request: Option<ThreadsafeFunction<NacosConfigReq>>,
+    on_response: Option<ThreadsafeFunction<NacosConfigResp>>,
+  },
+}
+
+impl NacosConfigFilter {
+  /// Build the filter of the entry, i.e. [`NacosConfigFilterEntry`], which only filters the configs matched.
+  pub(crate) fn new(
+    env: &Env,
+    object: JsObject,
+    timeout: Duration,
+    failures: Arc<FilterFailures>,
+  ) -> Result<Self> {
+    let entry = unsafe { NacosConfigFilterEntry::from_napi_value(env.raw(), object.raw())? };
+    let matcher = ConfigFilterMatcher::new(entry.data_id.as_deref(), entry.group.as_deref())?;
+    let func = match (entry.filter, entry.on_request, entry.on_response) {
+      (Some(filter), None, None) => ConfigFilterFunc::Tuple(filter.create_threadsafe_function(
+        0,
+        |ctx: ThreadSafeCallContext<(Option<NacosConfigReq>, Option<NacosConfigResp>)>| {
+          ctx.value.into_vec(ctx.env.raw())
+        },
+      )?),
+      (None, on_request, on_response) if on_request.is_some() || on_response.is_some() => {
+        ConfigFilterFunc::Hooks {
+          on_request: on_request
+            .map(|hook| create_hook(hook, &object))
+            .transpose()?,
+          on_response: on_response
+            .map(|hook| create_hook(hook, &object))
+            .transpose()?,
+        }
+      }
+      _ => {
+        return Err(Error::from_reason(
+          "config filter needs either filter, or onRequest and/or onResponse",
+        ));
+      }
+    };
+    Ok(NacosConfigFilter {
+      func,
+      matcher,
+      timeout,
+      failures,
+    })
+  }
+
+  /// Filter the request by js, None if it is not called.
+  async fn filter_request(
+    &self,
+    js_config_req: NacosConfigReq,
+  ) -> Option<std::result::Result<NacosConfigReq, String>> {
+    match &self.func {
+      ConfigFilterFunc::Tuple(func) => {
+        let ret = await_js_call(
+          func.call_async(Ok((Some(js_config_req), None))),
+          self.timeout,
+        )
+        .await;
+        Some(ret.and_then(
+          |(config_req, _): (Option<NacosConfigReq>, Option<NacosConfigResp>)| {
+            config_req.ok_or_else(|| "returns no ConfigReq".to_string())
+          },
+        ))
+      }
+      ConfigFilterFunc::Hooks { on_request, .. } => match on_request {
+        Some(func) => Some(await_js_call(func.call_async(Ok(js_config_req)), self.timeout).await),
+        None => None,
+      },
+    }
+  }
+
+  /// Filter the response by js, None if it is not called.
+  async fn filter_response(
+    &self,
+    js_config_resp: NacosConfigResp,
+  ) -> Option<std::result::Result<NacosConfigResp, String>> {
+    match &self.func {
+      ConfigFilterFunc::Tuple(func) => {
+        let ret = await_js_call(
+          func.call_async(Ok((None, Some(js_config_resp)))),
+          self.timeout,
+        )
+        .await;
+        Some(ret.and_then(
+          |(_, config_resp): (Option<NacosConfigReq>, Option<NacosConfigResp>)| {
+            config_resp.ok_or_else(|| "returns no ConfigResp".to_string())
+          },
+        ))
+      }
+      ConfigFilterFunc::Hooks { on_response, .. } => match on_response {
+        Some(func) => Some(await_js_call(func.call_async(Ok(js_config_resp)), self.timeout).await),
+        None => None,
+      },
+    }
+  }
+}
+
+/// Create the threadsafe function of hook, which is called as a method of its entry, i.e. `entry.onRequest(req)`.
+/// The threadsafe function calls with `(null, value)`, so it calls `hook.bind(entry).call` bound to the bound hook,
+/// i.e. `boundHook.call(null, value)`.
+fn create_hook<T: ToNapiValue + 'static>(
+  hook: JsFunction,
+  entry: &JsObject,
+) -> Result<ThreadsafeFunction<T>> {
+  let bind = |func: JsObject, this: &JsObject| {
+    let bind: JsFunction = func.get_named_property("bind")?;
+    JsFunction::try_from(bind.call(Some(&func), &[this])?)
+  };
+  let bound_hook = bind(hook.coerce_to_object()?, entry)?.coerce_to_object()?;
+  let call = bound_hook
+    .get_named_property::<JsFunction>("call")?
+    .coerce_to_object()?;
+  let bound_call = bind(call, &bound_hook)?;
+  bound_call.create_threadsafe_function(0, |ctx| Ok(vec![ctx.value]))
+}
+
+/// A config filter of the chain, which only filters the configs matched by dataId and group.
+/// It is either `filter` for both request and response, or `onRequest` and/or `onResponse`.
+#[napi(object, object_to_js = false)]
+pub struct NacosConfigFilterEntry {
+  /// The filter func, called with `(err, req, resp)` and returns `[req, resp]` or a Promise of it
+  #[napi(
+    ts_type = "(err: Error | null, req?: NacosConfigReq | undefined | null, resp?: NacosConfigResp | undefined | null) => any"
+  )]
+  pub filter: Option<JsFunction>,
+  /// Filter the request to publish, returns the req or a Promise of it
+  #[napi(ts_type = "(req: NacosConfigReq) => NacosConfigReq | Promise<NacosConfigReq>")]
+  pub on_request: Option<JsFunction>,
+  /// Filter the response got or listened, returns the resp or a Promise of it
+  #[napi(ts_type = "(resp: NacosConfigResp) => NacosConfigResp | Promise<NacosConfigResp>")]
+  pub on_response: Option<JsFunction>,
+  /// Glob of dataId, e.g. `cipher-*`, default all
+  pub data_id: Option<String>,
+  /// Glob of group, e.g. `DEFAULT_*`, default all
+  pub group: Option<String>,
+}
+
+/// Match the config by globs of dataId and group, None matches all.
+struct ConfigFilterMatcher {
+  data_id: Option<globset::GlobMatcher>,
+  group: Option<globset::GlobMatcher>,
+}
+
+impl ConfigFilterMatcher {
+  fn new(data_id: Option<&str>, group: Option<&str>) -> Result<Self> {
+    let compile = |glob: Option<&str>| {
+      glob
+        .map(|glob| {
+          globset::Glob::new(glob)
+            .map(|glob| glob.compile_matcher())
+            .map_err(|err| Error::from_reason(format!("invalid config filter glob: {err}")))
+        })
+        .transpose()
+    };
+    Ok(ConfigFilterMatcher {
+      data_id: compile(data_id)?,
+      group: compile(group)?,
+    })
+  }
+
+  fn is_match(&self, data_id: &str, group: &str) -> bool {
+    self
+      .data_id
+      .as_ref()
+      .is_none_or(|matcher| matcher.is_match(data_id))
+      && self
+        .group
+        .as_ref()
+        .is_none_or(|matcher| matcher.is_match(group))
+  }
 }
 
 #[async_trait::async_trait]
@@ -14,7 +198,11 @@ impl nacos_sdk::api::plugin::ConfigFilter for NacosConfigFilter {
     config_req: Option<&mut nacos_sdk::api::plugin::ConfigReq>,
     config_resp: Option<&mut nacos_sdk::api::plugin::ConfigResp>,
   ) {
-    if let Some(config_req) = config_req {
+    if let Some(config_req) = config_req
+      && self
+        .matcher
+        .is_match(&config_req.data_id, &config_req.group)
+    {
       let js_config_req = NacosConfigReq {
         data_id: config_req.data_id.clone(),
         group: config_req.group.clone(),
@@ -23,21 +211,26 @@ impl nacos_sdk::api::plugin::ConfigFilter for NacosConfigFilter {
         encrypted_data_key: config_req.encrypted_data_key.clone(),
       };
 
-      let (after_js_config_req, _): (Option<NacosConfigReq>, Option<NacosConfigResp>) = self
-        .func
-        .call_async(Ok((Some(js_config_req), None)))
-        .await
-        .unwrap();
-
-      let ret = after_js_config_req.unwrap();
-      config_req.data_id = ret.data_id;
-      config_req.group = ret.group;
-      config_req.namespace = ret.namespace;
-      config_req.content = ret.content;
-      config_req.encrypted_data_key = ret.encrypted_data_key;
+      match self.filter_request(js_config_req).await {
+        Some(Ok(ret)) => {
+          config_req.data_id = ret.data_id;
+          config_req.group = ret.group;
+          config_req.namespace = ret.namespace;
+          config_req.content = ret.content;
+          config_req.encrypted_data_key = ret.encrypted_data_key;
+        }
+        Some(Err(err)) => self
+          .failures
+          .request_failed(config_req, format!("configFilter failed: {err}")),
+        None => {}
+      }
     }
 
-    if let Some(config_resp) = config_resp {
+    if let Some(config_resp) = config_resp
+      && self
+        .matcher
+        .is_match(&config_resp.data_id, &config_resp.group)
+    {
       let js_config_resp = NacosConfigResp {
         data_id: config_resp.data_id.clone(),
         group: config_resp.group.clone(),
@@ -46,18 +239,327 @@ impl nacos_sdk::api::plugin::ConfigFilter for NacosConfigFilter {
         encrypted_data_key: config_resp.encrypted_data_key.clone(),
       };
 
-      let (_, after_js_config_resp): (Option<NacosConfigReq>, Option<NacosConfigResp>) = self
-        .func
-        .call_async(Ok((None, Some(js_config_resp))))
-        .await
-        .unwrap();
-
-      let ret = after_js_config_resp.unwrap();
-      config_resp.data_id = ret.data_id;
-      config_resp.group = ret.group;
-      config_resp.namespace = ret.namespace;
-      config_resp.content = ret.content;
-      config_resp.encrypted_data_key = ret.encrypted_data_key;
+      match self.filter_response(js_config_resp).await {
+        Some(Ok(ret)) => {
+          config_resp.data_id = ret.data_id;
+          config_resp.group = ret.group;
+          config_resp.namespace = ret.namespace;
+          config_resp.content = ret.content;
+          config_resp.encrypted_data_key = ret.encrypted_data_key;
+        }
+        Some(Err(err)) => self
+          .failures
+          .response_failed(format!("configFilter failed: {err}")),
+        None => {}
+      }
+    }
+  }
+}
+
+/// Default timeout of the js func of config filters.
+pub(crate) const DEFAULT_FILTER_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Await the call of js func, and the Promise it returns if any, fail if it takes longer than the timeout.
+pub(crate) async fn await_js_call<R>(
+  call: impl std::future::Future<Output = Result<Either<Promise<R>, R>>>,
+  timeout: Duration,
+) -> std::result::Result<R, String>
+where
+  R: 'static + Send + FromNapiValue + TypeName + ValidateNapiValue,
+{
+  let call = async {
+    match call.await? {
+      Either::A(promise) => promise.await,
+      Either::B(ret) => Ok(ret),
+    }
+  };
+  match tokio::time::timeout(timeout, call).await {
+    Ok(ret) => ret.map_err(|err| err.reason),
+    Err(_) => Err(format!("timed out after {}ms", timeout.as_millis())),
+  }
+}
+
+tokio::task_local! {
+  /// The error of config filters which fail closed, in the task of a client operation.
+  static FILTER_ERROR: RefCell<Option<String>>;
+}
+
+/// Run `fut` of a client operation, and take the first error of config filters which fail closed in it.
+pub(crate) async fn catch_filter_error<F: std::future::Future>(
+  fut: F,
+) -> (F::Output, Option<String>) {
+  FILTER_ERROR
+    .scope(RefCell::new(None), async move {
+      let output = fut.await;
+      (output, FILTER_ERROR.with(|filter_err| filter_err.take()))
+    })
+    .await
+}
+
+/// How the failures of config filters are handled, pass the content through as it is, or fail closed:
+/// the client operation fails with the error, and the listeners skip the notification.
+pub(crate) struct FilterFailures {
+  pass_through: bool,
+  /// The md5 of the configs failed to filter out of client operations, i.e. to be notified to listeners,
+  /// by dataId and group. Only the filters for notify set it, see [`ConfigFilterChain`].
+  skipped: Mutex<HashMap<(String, String), HashSet<String>>>,
+}
+
+impl FilterFailures {
+  pub(crate) fn new(pass_through: bool) -> Self {
+    FilterFailures {
+      pass_through,
+      skipped: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Fail the client operation, even if it passes through.
+  pub(crate) fn fail(&self, err: String) {
+    let _ = FILTER_ERROR.try_with(|filter_err| {
+      filter_err.borrow_mut().get_or_insert(err);
+    });
+  }
+
+  /// The request failed to filter, never publish it if fail closed, see [`ConfigFilterChain::publish`].
+  pub(crate) fn request_failed(
+    &self,
+    config_req: &mut nacos_sdk::api::plugin::ConfigReq,
+    err: String,
+  ) {
+    if !self.pass_through {
+      config_req.content.clear();
+      self.fail(err);
+    }
+  }
+
+  /// The response failed to filter, fail the client operation if fail closed, or the notify which
+  /// the listeners skip then, see [`ConfigFilterChain`].
+  pub(crate) fn response_failed(&self, err: String) {
+    if !self.pass_through {
+      self.fail(err);
+    }
+  }
+
+  /// The config of md5 is filtered to be notified, skip it by the listeners if it failed.
+  fn filtered_for_notify(&self, data_id: &str, group: &str, md5: &str, failed: bool) {
+    let mut skipped = self.skipped.lock().unwrap();
+    let key = (data_id.to_string(), group.to_string());
+    if failed {
+      skipped.entry(key).or_default().insert(md5.to_string());
+    } else if let Some(md5s) = skipped.get_mut(&key) {
+      md5s.remove(md5);
+      if md5s.is_empty() {
+        skipped.remove(&key);
+      }
+    }
+  }
+
+  /// Whether the listeners skip the config of md5, as it failed to filter.
+  pub(crate) fn is_skipped(&self, data_id: &str, group: &str, md5: &str) -> bool {
+    self
+      .skipped
+      .lock()
+      .unwrap()
+      .get(&(data_id.to_string(), group.to_string()))
+      .is_some_and(|md5s| md5s.contains(md5))
+  }
+}
+
+tokio::task_local! {
+  /// The request filtered ahead by [`ConfigFilterChain::publish`], in the task of publishing.
+  static FILTERED_REQUEST: RefCell<Option<nacos_sdk::api::plugin::ConfigReq>>;
+}
+
+/// The config filters of client in order, which is the only filter of nacos-sdk.
+/// nacos-sdk filters the request inside publish and sends it even if the filters fail,
+/// so the request is filtered ahead by [`ConfigFilterChain::publish`], and nacos-sdk sends it as it is.
+/// The response filtered out of client operations is to be notified, its failure is kept by md5 for the listeners.
+#[derive(Clone)]
+pub(crate) struct ConfigFilterChain {
+  namespace: String,
+  filters: Arc<Vec<Box<dyn nacos_sdk::api::plugin::ConfigFilter>>>,
+  failures: Arc<FilterFailures>,
+  /// The last response of each config before the filters, by dataId and group.
+  unfiltered: Arc<Mutex<HashMap<(String, String), UnfilteredResp>>>,
+}
+
+/// The response of config before the config filters, e.g. encrypted, so that it is the one kept in the snapshot.
+#[derive(Clone)]
+pub(crate) struct UnfilteredResp {
+  /// The md5 of the content before the filters, i.e. the md5 of config
+  pub(crate) md5: String,
+  /// The content and encryptedDataKey before the filters, None if the filters do not change the content
+  pub(crate) content: Option<(String, String)>,
+}
+
+impl ConfigFilterChain {
+  pub(crate) fn new(
+    namespace: String,
+    filters: Vec<Box<dyn nacos_sdk::api::plugin::ConfigFilter>>,
+    failures: Arc<FilterFailures>,
+  ) -> Self {
+    ConfigFilterChain {
+      namespace,
+      filters: Arc::new(filters),
+      failures,
+      unfiltered: Arc::new(Mutex::new(HashMap::new())),
+    }
+  }
+
+  /// The last response of the config before the filters, got or notified.
+  pub(crate) fn unfiltered(&self, data_id: &str, group: &str) -> Option<UnfilteredResp> {
+    self
+      .unfiltered
+      .lock()
+      .unwrap()
+      .get(&(data_id.to_string(), group.to_string()))
+      .cloned()
+  }
+
+  /// Filter the content with encryptedDataKey before the filters, e.g. read from the snapshot,
+  /// the same as the response got, fail with the first error of config filters which fail closed.
+  pub(crate) async fn filter_unfiltered(
+    &self,
+    data_id: &str,
+    group: &str,
+    content: String,
+    encrypted_data_key: String,
+  ) -> std::result::Result<String, String> {
+    let mut config_resp = nacos_sdk::api::plugin::ConfigResp::new(
+      data_id.to_string(),
+      group.to_string(),
+      self.namespace.clone(),
+      content,
+      encrypted_data_key,
+    );
+    let ((), filter_err) = catch_filter_error(self.filter_response(&mut config_resp)).await;
+    match filter_err {
+      Some(filter_err) => Err(filter_err),
+      None => Ok(config_resp.content),
+    }
+  }
+
+  /// Filter the content with the encryptedDataKey of publish options, then `publish` the dataId, group and content
+  /// filtered. Take the first error of config filters which fail closed, it is never published if the request fails.
+  pub(crate) async fn publish<F>(
+    &self,
+    data_id: String,
+    group: String,
+    content: String,
+    encrypted_data_key: Option<String>,
+    publish: impl FnOnce(String, String, String) -> F,
+  ) -> (nacos_sdk::api::error::Result<bool>, Option<String>)
+  where
+    F: std::future::Future<Output = nacos_sdk::api::error::Result<bool>>,
+  {
+    catch_filter_error(async move {
+      let mut config_req = nacos_sdk::api::plugin::ConfigReq::new(
+        data_id,
+        group,
+        self.namespace.clone(),
+        content,
+        encrypted_data_key.unwrap_or_default(),
+      );
+      self.filter_request(&mut config_req).await;
+      if FILTER_ERROR.with(|filter_err| filter_err.borrow().is_some()) {
+        return Ok(false);
+      }
+      send_filtered(config_req, publish).await
+    })
+    .await
+  }
+
+  /// `publish` the content with the encryptedDataKey as it is, without the filters,
+  /// e.g. the content imported is the one stored, which is encrypted already.
+  pub(crate) async fn publish_unfiltered<F>(
+    &self,
+    data_id: String,
+    group: String,
+    content: String,
+    encrypted_data_key: Option<String>,
+    publish: impl FnOnce(String, String, String) -> F,
+  ) -> nacos_sdk::api::error::Result<bool>
+  where
+    F: std::future::Future<Output = nacos_sdk::api::error::Result<bool>>,
+  {
+    let config_req = nacos_sdk::api::plugin::ConfigReq::new(
+      data_id,
+      group,
+      self.namespace.clone(),
+      content,
+      encrypted_data_key.unwrap_or_default(),
+    );
+    send_filtered(config_req, publish).await
+  }
+
+  async fn filter_request(&self, config_req: &mut nacos_sdk::api::plugin::ConfigReq) {
+    for filter in self.filters.iter() {
+      filter.filter(Some(config_req), None).await;
+    }
+  }
+
+  async fn filter_response(&self, config_resp: &mut nacos_sdk::api::plugin::ConfigResp) {
+    for filter in self.filters.iter() {
+      filter.filter(None, Some(config_resp)).await;
+    }
+  }
+}
+
+/// `publish` the request filtered, which nacos-sdk sends as it is, see [`ConfigFilterChain`].
+async fn send_filtered<F>(
+  config_req: nacos_sdk::api::plugin::ConfigReq,
+  publish: impl FnOnce(String, String, String) -> F,
+) -> nacos_sdk::api::error::Result<bool>
+where
+  F: std::future::Future<Output = nacos_sdk::api::error::Result<bool>>,
+{
+  let publishing = publish(
+    config_req.data_id.clone(),
+    config_req.group.clone(),
+    config_req.content.clone(),
+  );
+  FILTERED_REQUEST
+    .scope(RefCell::new(Some(config_req)), publishing)
+    .await
+}
+
+#[async_trait::async_trait]
+impl nacos_sdk::api::plugin::ConfigFilter for ConfigFilterChain {
+  async fn filter(
+    &self,
+    config_req: Option<&mut nacos_sdk::api::plugin::ConfigReq>,
+    config_resp: Option<&mut nacos_sdk::api::plugin::ConfigResp>,
+  ) {
+    if let Some(config_req) = config_req {
+      match FILTERED_REQUEST.try_with(|filtered| filtered.take()) {
+        Ok(Some(filtered)) => *config_req = filtered,
+        _ => self.filter_request(config_req).await,
+      }
+    }
+
+    if let Some(config_resp) = config_resp {
+      let content = config_resp.content.clone();
+      let encrypted_data_key = config_resp.encrypted_data_key.clone();
+      let md5 = format!("{:x}", md5::compute(&content));
+      if FILTER_ERROR.try_with(|_| ()).is_ok() {
+        self.filter_response(config_resp).await;
+      } else {
+        let ((), filter_err) = catch_filter_error(self.filter_response(config_resp)).await;
+        self.failures.filtered_for_notify(
+          &config_resp.data_id,
+          &config_resp.group,
+          &md5,
+          filter_err.is_some(),
+        );
+      }
+      let unfiltered = UnfilteredResp {
+        md5,
+        content: (config_resp.content != content).then_some((content, encrypted_data_key)),
+      };
+      self.unfiltered.lock().unwrap().insert(
+        (config_resp.data_id.clone(), config_resp.group.clone()),
+        unfiltered,
+      );
     }
   }
 }
@@ -0,0 +1,157 @@
+use napi::{Env, JsFunction, Ref, Result};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Keep the listeners registered by a client, so that they can be found and removed again.
+///
+/// Every entry holds a reference to the js callback, identity of js function is `===`.
+pub(crate) struct ListenerRegistry<K, L> {
+  entries: Mutex<HashMap<K, Vec<ListenerEntry<L>>>>,
+  next_id: AtomicU64,
+}
+
+struct ListenerEntry<L> {
+  id: u64,
+  js_func: Option<Ref<()>>,
+  listener: Arc<L>,
+}
+
+impl<L> Drop for ListenerEntry<L> {
+  fn drop(&mut self) {
+    // The references are released with env when the entry is removed or the client is finalized, see
+    // `release_js_funcs`. One left here (e.g. the release failed) can not be released without env, forget it.
+    if let Some(js_func) = self.js_func.take() {
+      std::mem::forget(js_func);
+    }
+  }
+}
+
+impl<K: Eq + Hash, L> ListenerRegistry<K, L> {
+  pub(crate) fn new() -> Self {
+    ListenerRegistry {
+      entries: Mutex::new(HashMap::new()),
+      next_id: AtomicU64::new(1),
+    }
+  }
+
+  /// Register a listener, which bind with the js callback if any (a watcher has not). Return the id of entry.
+  pub(crate) fn insert(&self, key: K, js_func: Option<Ref<()>>, listener: Arc<L>) -> u64 {
+    let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+    let mut entries = self.entries.lock().unwrap();
+    entries.entry(key).or_default().push(ListenerEntry {
+      id,
+      js_func,
+      listener,
+    });
+    id
+  }
+
+  /// All listeners registered with the key.
+  pub(crate) fn get(&self, key: &K) -> Vec<Arc<L>> {
+    let entries = self.entries.lock().unwrap();
+    entries
+      .get(key)
+      .map(|list| list.iter().map(|entry| entry.listener.clone()).collect())
+      .unwrap_or_default()
+  }
+
+  /// Whether any listener registered with the key.
+  pub(crate) fn contains_key(&self, key: &K) -> bool {
+    self.entries.lock().unwrap().contains_key(key)
+  }
+
+  /// Whether the entry of id is still registered with the key.
+  pub(crate) fn contains_id(&self, key: &K, id: u64) -> bool {
+    let entries = self.entries.lock().unwrap();
+    entries
+      .get(key)
+      .is_some_and(|list| list.iter().any(|entry| entry.id == id))
+  }
+
+  /// Remove the first listener which bind with the js callback, return it for removing from nacos-sdk.
+  pub(crate) fn remove_by_func(
+    &self,
+    env: &Env,
+    key: &K,
+    js_func: &JsFunction,
+  ) -> Result<Option<Arc<L>>> {
+    let mut entries = self.entries.lock().unwrap();
+    let Some(list) = entries.get(key) else {
+      return Ok(None);
+    };
+
+    let mut found = None;
+    for entry in list.iter() {
+      if let Some(js_ref) = entry.js_func.as_ref() {
+        let registered: JsFunction = env.get_reference_value(js_ref)?;
+        if env.strict_equals(registered, js_func)? {
+          found = Some(entry.id);
+          break;
+        }
+      }
+    }
+
+    match found {
+      Some(id) => Self::remove_entry(&mut entries, env, key, id),
+      None => Ok(None),
+    }
+  }
+
+  /// Remove the listener by id of entry, return it for removing from nacos-sdk.
+  pub(crate) fn remove_by_id(&self, env: &Env, key: &K, id: u64) -> Result<Option<Arc<L>>> {
+    let mut entries = self.entries.lock().unwrap();
+    Self::remove_entry(&mut entries, env, key, id)
+  }
+
+  /// Release the references to the js callbacks, in the finalizer of client, so that they are not GC roots.
+  /// The entries are kept, so that the subscriptions still remove their listeners from nacos-sdk by id.
+  pub(crate) fn release_js_funcs(&self, env: Env) -> Result<()> {
+    let mut entries = self.entries.lock().unwrap();
+    for entry in entries.values_mut().flatten() {
+      if let Some(mut js_ref) = entry.js_func.take() {
+        js_ref.unref(env)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Remove the listener by id of entry which not bind with js callback, so env is not needed.
+  pub(crate) fn remove_unbound(&self, key: &K, id: u64) -> Option<Arc<L>> {
+    let mut entries = self.entries.lock().unwrap();
+    let list = entries.get_mut(key)?;
+    let idx = list
+      .iter()
+      .position(|entry| entry.id == id && entry.js_func.is_none())?;
+
+    let entry = list.remove(idx);
+    if list.is_empty() {
+      entries.remove(key);
+    }
+    Some(entry.listener.clone())
+  }
+
+  fn remove_entry(
+    entries: &mut HashMap<K, Vec<ListenerEntry<L>>>,
+    env: &Env,
+    key: &K,
+    id: u64,
+  ) -> Result<Option<Arc<L>>> {
+    let Some(list) = entries.get_mut(key) else {
+      return Ok(None);
+    };
+    let Some(idx) = list.iter().position(|entry| entry.id == id) else {
+      return Ok(None);
+    };
+
+    let mut entry = list.remove(idx);
+    if list.is_empty() {
+      entries.remove(key);
+    }
+    if let Some(mut js_ref) = entry.js_func.take() {
+      js_ref.unref(*env)?;
+    }
+    Ok(Some(entry.listener.clone()))
+  }
+}
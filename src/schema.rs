@@ -0,0 +1,206 @@
+use napi::{JsObject, bindgen_prelude::*, threadsafe_function::*};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// JSON Schemas registered by `registerSchema`, which the config content parsed by its format must match.
+pub(crate) struct SchemaRegistry {
+  schemas: Mutex<HashMap<(String, String), ConfigSchema>>,
+  on_invalid: Mutex<Option<Arc<ThreadsafeFunction<NacosInvalidConfigEvent>>>>,
+}
+
+struct ConfigSchema {
+  validator: Arc<jsonschema::Validator>,
+  /// The last config which matches the schema, kept as current if the new one does not.
+  last_valid: Option<crate::NacosConfigResponse>,
+  /// Md5 of the last invalid config reported, so that it is reported only once.
+  reported_md5: Option<String>,
+}
+
+/// The config does not match the schema, with the last valid one if any.
+pub(crate) struct SchemaRejected {
+  pub(crate) errors: Vec<String>,
+  pub(crate) last_valid: Option<Box<crate::NacosConfigResponse>>,
+}
+
+impl SchemaRegistry {
+  pub(crate) fn new() -> Self {
+    SchemaRegistry {
+      schemas: Mutex::new(HashMap::new()),
+      on_invalid: Mutex::new(None),
+    }
+  }
+
+  /// Register the schema of config, replace the former one if any.
+  pub(crate) fn register(
+    &self,
+    data_id: String,
+    group: String,
+    schema: &serde_json::Value,
+  ) -> std::result::Result<(), String> {
+    let validator = jsonschema::validator_for(schema).map_err(|err| err.to_string())?;
+    self.schemas.lock().unwrap().insert(
+      (data_id, group),
+      ConfigSchema {
+        validator: Arc::new(validator),
+        last_valid: None,
+        reported_md5: None,
+      },
+    );
+    Ok(())
+  }
+
+  pub(crate) fn unregister(&self, data_id: String, group: String) -> bool {
+    self
+      .schemas
+      .lock()
+      .unwrap()
+      .remove(&(data_id, group))
+      .is_some()
+  }
+
+  /// Whether the schema of config is registered.
+  pub(crate) fn is_registered(&self, data_id: &str, group: &str) -> bool {
+    self
+      .schemas
+      .lock()
+      .unwrap()
+      .contains_key(&(data_id.to_string(), group.to_string()))
+  }
+
+  pub(crate) fn set_on_invalid(&self, func: Option<ThreadsafeFunction<NacosInvalidConfigEvent>>) {
+    *self.on_invalid.lock().unwrap() = func.map(Arc::new);
+  }
+
+  /// Validate the content to publish, content's type is the type of publish or the extension of dataId.
+  pub(crate) fn validate(
+    &self,
+    data_id: &str,
+    group: &str,
+    content_type: Option<&str>,
+    content: &str,
+  ) -> std::result::Result<(), Vec<String>> {
+    let validator = {
+      let schemas = self.schemas.lock().unwrap();
+      match schemas.get(&(data_id.to_string(), group.to_string())) {
+        Some(schema) => schema.validator.clone(),
+        None => return Ok(()),
+      }
+    };
+    let format = crate::config_format(content_type.unwrap_or_default(), data_id);
+    validate_content(&validator, &format, content)
+  }
+
+  /// Check the config got or received, remember it if valid, otherwise report it to `onInvalidConfig` once.
+  pub(crate) fn check(
+    &self,
+    conf_resp: crate::NacosConfigResponse,
+  ) -> std::result::Result<crate::NacosConfigResponse, SchemaRejected> {
+    let key = (conf_resp.data_id.clone(), conf_resp.group.clone());
+    let Some(validator) = self
+      .schemas
+      .lock()
+      .unwrap()
+      .get(&key)
+      .map(|schema| schema.validator.clone())
+    else {
+      return Ok(conf_resp);
+    };
+
+    let format = crate::config_format(&conf_resp.content_type, &conf_resp.data_id);
+    let checked = validate_content(&validator, &format, &conf_resp.content);
+
+    let mut schemas = self.schemas.lock().unwrap();
+    let Some(schema) = schemas.get_mut(&key) else {
+      return Ok(conf_resp);
+    };
+    let errors = match checked {
+      Ok(()) => {
+        schema.last_valid = Some(conf_resp.clone());
+        schema.reported_md5 = None;
+        return Ok(conf_resp);
+      }
+      Err(errors) => errors,
+    };
+    let last_valid = schema.last_valid.clone();
+    if schema.reported_md5.as_ref() != Some(&conf_resp.md5) {
+      schema.reported_md5 = Some(conf_resp.md5.clone());
+      if let Some(func) = self.on_invalid.lock().unwrap().as_ref() {
+        let event = NacosInvalidConfigEvent {
+          invalid: conf_resp,
+          errors: errors.clone(),
+          current: last_valid.clone(),
+        };
+        func.call(Ok(event), ThreadsafeFunctionCallMode::NonBlocking);
+      }
+    }
+    Err(SchemaRejected {
+      errors,
+      last_valid: last_valid.map(Box::new),
+    })
+  }
+}
+
+/// Parse the content by format, and validate it by the schema.
+fn validate_content(
+  validator: &jsonschema::Validator,
+  format: &str,
+  content: &str,
+) -> std::result::Result<(), Vec<String>> {
+  let value = match crate::parse_structured(format, content) {
+    Some(Ok(value)) => value,
+    Some(Err(parse_err)) => {
+      return Err(vec![format!(
+        "parse {format} failed: {}",
+        parse_err.message
+      )]);
+    }
+    None => return Err(vec![format!("unsupported format {format}")]),
+  };
+  let errors: Vec<String> = validator
+    .iter_errors(&value)
+    .map(|err| match err.instance_path.to_string() {
+      path if path.is_empty() => err.to_string(),
+      path => format!("{path}: {err}"),
+    })
+    .collect();
+  if errors.is_empty() {
+    Ok(())
+  } else {
+    Err(errors)
+  }
+}
+
+/// The config does not match the schema, as js Error with code 'ConfigSchemaInvalid' and `errors`.
+pub(crate) fn create_schema_error(
+  env: &Env,
+  data_id: &str,
+  group: &str,
+  errors: &[String],
+) -> Result<JsObject> {
+  let mut error = crate::create_coded_error(
+    env,
+    "ConfigSchemaInvalid",
+    format!(
+      "config does not match the schema, dataId={data_id}, group={group}: {}",
+      errors.join("; ")
+    ),
+  )?;
+  error.set_named_property("dataId", env.create_string(data_id)?)?;
+  error.set_named_property("group", env.create_string(group)?)?;
+  let mut js_errors = env.create_array_with_length(errors.len())?;
+  for (idx, err) in errors.iter().enumerate() {
+    js_errors.set_element(idx as u32, env.create_string(err)?)?;
+  }
+  error.set_named_property("errors", js_errors)?;
+  Ok(error)
+}
+
+#[napi(object)]
+pub struct NacosInvalidConfigEvent {
+  /// The config which does not match the schema, held back from listeners
+  pub invalid: crate::NacosConfigResponse,
+  /// Why it does not match
+  pub errors: Vec<String>,
+  /// The last valid config which is kept as current, absent if none
+  pub current: Option<crate::NacosConfigResponse>,
+}
@@ -0,0 +1,241 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// The server read times out in failover mode 'onError', then the snapshot is served.
+pub(crate) const FAILOVER_READ_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// When the configs are read from the snapshots.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum FailoverMode {
+  /// Never, the snapshots are only written.
+  Off,
+  /// The server can not be reached.
+  OnError,
+  /// Always, the server is read only if there is no snapshot, or to refresh the snapshot in background.
+  Always,
+}
+
+/// Local snapshots of configs, written on every successful get and notify, one file per config.
+/// The content is the one before the config filters, e.g. encrypted, and they are applied when it is read.
+pub(crate) struct ConfigSnapshots {
+  dir: PathBuf,
+  mode: FailoverMode,
+  filters: crate::ConfigFilterChain,
+  /// The writes of snapshots, done in order by [`write_snapshots`].
+  writes: mpsc::UnboundedSender<(SnapshotWrite, oneshot::Sender<()>)>,
+}
+
+impl ConfigSnapshots {
+  pub(crate) fn new(
+    dir: String,
+    namespace: &str,
+    mode: Option<String>,
+    filters: crate::ConfigFilterChain,
+  ) -> napi::Result<Self> {
+    let mode = match mode.as_deref() {
+      None | Some("onError") => FailoverMode::OnError,
+      Some("off") => FailoverMode::Off,
+      Some("always") => FailoverMode::Always,
+      Some(other) => {
+        return Err(napi::Error::from_reason(format!(
+          "unsupported config failover mode {other}, expected 'off', 'onError' or 'always'"
+        )));
+      }
+    };
+    let namespace = if namespace.is_empty() {
+      "public"
+    } else {
+      namespace
+    };
+    let (writes, pending) = mpsc::unbounded_channel();
+    napi::bindgen_prelude::spawn(write_snapshots(pending));
+    Ok(ConfigSnapshots {
+      dir: PathBuf::from(dir).join(encode_name(namespace)),
+      mode,
+      filters,
+      writes,
+    })
+  }
+
+  pub(crate) fn mode(&self) -> FailoverMode {
+    self.mode
+  }
+
+  fn path(&self, data_id: &str, group: &str) -> PathBuf {
+    self.dir.join(encode_name(group)).join(encode_name(data_id))
+  }
+
+  fn write(&self, write: SnapshotWrite) -> SnapshotWritten {
+    let (written, done) = oneshot::channel();
+    let _ = self.writes.send((write, written));
+    SnapshotWritten(Some(done))
+  }
+
+  /// Write the snapshot in background, atomically by a temp file renamed to it. Failures are ignored, as it is best effort.
+  /// It is skipped if the content before the config filters is unknown, e.g. the config changed while it is got.
+  pub(crate) fn save(
+    &self,
+    config_resp: &nacos_sdk::api::config::ConfigResponse,
+  ) -> SnapshotWritten {
+    let unfiltered = self
+      .filters
+      .unfiltered(config_resp.data_id(), config_resp.group())
+      .filter(|unfiltered| unfiltered.md5 == *config_resp.md5());
+    let Some(unfiltered) = unfiltered else {
+      return SnapshotWritten(None);
+    };
+    let mut snapshot = serde_json::json!({
+      "dataId": config_resp.data_id(),
+      "group": config_resp.group(),
+      "namespace": config_resp.namespace(),
+      "content": config_resp.content(),
+      "contentType": config_resp.content_type(),
+      "md5": config_resp.md5(),
+    });
+    if let Some((content, encrypted_data_key)) = unfiltered.content {
+      snapshot["content"] = content.into();
+      snapshot["encryptedDataKey"] = encrypted_data_key.into();
+      snapshot["unfiltered"] = true.into();
+    }
+    self.write(SnapshotWrite::Save {
+      path: self.path(config_resp.data_id(), config_resp.group()),
+      snapshot: snapshot.to_string(),
+    })
+  }
+
+  /// Read the snapshot with the config filters applied, None if there is none, it is broken, or the filters fail.
+  pub(crate) async fn load(
+    &self,
+    data_id: &str,
+    group: &str,
+  ) -> Option<nacos_sdk::api::config::ConfigResponse> {
+    let snapshot = tokio::fs::read_to_string(self.path(data_id, group))
+      .await
+      .ok()?;
+    let snapshot: serde_json::Value = serde_json::from_str(&snapshot).ok()?;
+    let field = |name: &str| {
+      snapshot
+        .get(name)
+        .and_then(serde_json::Value::as_str)
+        .map(str::to_string)
+    };
+    let content = if snapshot.get("unfiltered") == Some(&serde_json::Value::Bool(true)) {
+      self
+        .filters
+        .filter_unfiltered(
+          data_id,
+          group,
+          field("content")?,
+          field("encryptedDataKey").unwrap_or_default(),
+        )
+        .await
+        .ok()?
+    } else {
+      field("content")?
+    };
+    Some(nacos_sdk::api::config::ConfigResponse::new(
+      data_id.to_string(),
+      group.to_string(),
+      field("namespace")?,
+      content,
+      field("contentType")?,
+      field("md5")?,
+    ))
+  }
+
+  /// The config does not exist anymore, so neither does the snapshot.
+  pub(crate) fn remove(&self, data_id: &str, group: &str) -> SnapshotWritten {
+    self.write(SnapshotWrite::Remove(self.path(data_id, group)))
+  }
+}
+
+/// The write of snapshot is done in background, wait for it if the snapshot is read after. None if it is skipped.
+pub(crate) struct SnapshotWritten(Option<oneshot::Receiver<()>>);
+
+impl SnapshotWritten {
+  pub(crate) async fn wait(self) {
+    if let Some(done) = self.0 {
+      let _ = done.await;
+    }
+  }
+}
+
+enum SnapshotWrite {
+  Save { path: PathBuf, snapshot: String },
+  Remove(PathBuf),
+}
+
+impl SnapshotWrite {
+  /// Do the write by the blocking file ops, `seq` is the suffix of the temp file,
+  /// so that the writes of the same config by other clients do not clash.
+  fn apply(self, seq: u64) {
+    match self {
+      SnapshotWrite::Save { path, snapshot } => {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+          return;
+        };
+        let tmp = parent.join(format!(
+          ".{}.{}-{seq}.tmp",
+          name.to_string_lossy(),
+          std::process::id(),
+        ));
+        let written = std::fs::create_dir_all(parent)
+          .and_then(|_| std::fs::write(&tmp, snapshot))
+          .and_then(|_| std::fs::rename(&tmp, &path));
+        if written.is_err() {
+          let _ = std::fs::remove_file(&tmp);
+        }
+      }
+      SnapshotWrite::Remove(path) => {
+        let _ = std::fs::remove_file(path);
+      }
+    }
+  }
+}
+
+/// Do the writes of snapshots one by one in order, off the async threads, until the snapshots are dropped.
+async fn write_snapshots(
+  mut writes: mpsc::UnboundedReceiver<(SnapshotWrite, oneshot::Sender<()>)>,
+) {
+  static SEQ: AtomicU64 = AtomicU64::new(0);
+  while let Some((write, written)) = writes.recv().await {
+    let seq = SEQ.fetch_add(1, Ordering::Relaxed);
+    let _ = tokio::task::spawn_blocking(move || write.apply(seq)).await;
+    let _ = written.send(());
+  }
+}
+
+/// Encode the name as a file name, the chars other than `[A-Za-z0-9._-]` are percent-encoded,
+/// and so is a leading `.`, so that the encoded names are never hidden ones, which are left to the temp files
+/// and e.g. the meta of [`crate::FileConfigStore`], nor `.` and `..`.
+pub(crate) fn encode_name(name: &str) -> String {
+  let mut encoded = String::with_capacity(name.len());
+  for (i, byte) in name.bytes().enumerate() {
+    match byte {
+      b'.' if i == 0 => encoded.push_str("%2E"),
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'_' | b'-' => encoded.push(byte as char),
+      _ => encoded.push_str(&format!("%{byte:02X}")),
+    }
+  }
+  encoded
+}
+
+/// Decode the file name encoded by [`encode_name`], None if it is not encoded by it.
+pub(crate) fn decode_name(encoded: &str) -> Option<String> {
+  let bytes = encoded.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    if bytes[i] == b'%' {
+      let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+      decoded.push(u8::from_str_radix(hex, 16).ok()?);
+      i += 3;
+    } else {
+      decoded.push(bytes[i]);
+      i += 1;
+    }
+  }
+  String::from_utf8(decoded).ok()
+}
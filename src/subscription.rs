@@ -0,0 +1,59 @@
+use napi::{JsFunction, JsObject, JsUnknown, ValueType, bindgen_prelude::*};
+
+/// The listener behind a [`Subscription`], implemented by config and naming clients.
+pub(crate) trait SubscriptionTarget: Send + Sync {
+  /// Whether the listener is still registered.
+  fn is_active(&self) -> bool;
+
+  /// Remove the listener, do nothing if it has been removed.
+  fn dispose(&self, env: &Env) -> Result<()>;
+}
+
+/// Handle of a listener which returned by `addListener` or `subscribe`, remove the listener on its own.
+/// It is a disposable object as well, e.g. `using sub = await client.addListener(...)`.
+#[napi]
+pub struct Subscription {
+  target: Box<dyn SubscriptionTarget>,
+}
+
+#[napi]
+impl Subscription {
+  /// Whether the listener is still active, false after disposed or removed.
+  #[napi(getter)]
+  pub fn active(&self) -> bool {
+    self.target.is_active()
+  }
+
+  /// Remove the listener, call it more than once is fine.
+  #[napi]
+  pub fn dispose(&self, env: Env) -> Result<()> {
+    self.target.dispose(&env)
+  }
+}
+
+impl Subscription {
+  pub(crate) fn new(target: impl SubscriptionTarget + 'static) -> Self {
+    Subscription {
+      target: Box::new(target),
+    }
+  }
+
+  /// Into js object, and set `[Symbol.dispose]` if the node version supports it.
+  pub(crate) fn into_js_object(self, env: &Env) -> Result<JsObject> {
+    let mut object = self.into_instance(*env)?.as_object(*env);
+    set_dispose_symbol(env, &mut object)?;
+    Ok(object)
+  }
+}
+
+/// Set `[Symbol.dispose]` as the `dispose` method of object, if the node version supports it.
+pub(crate) fn set_dispose_symbol(env: &Env, object: &mut JsObject) -> Result<()> {
+  let symbol: JsFunction = env.get_global()?.get_named_property("Symbol")?;
+  let symbol = symbol.coerce_to_object()?;
+  let dispose_symbol: JsUnknown = symbol.get_named_property("dispose")?;
+  if dispose_symbol.get_type()? == ValueType::Symbol {
+    let dispose: JsFunction = object.get_named_property("dispose")?;
+    object.set_property(dispose_symbol, dispose)?;
+  }
+  Ok(())
+}
@@ -0,0 +1,187 @@
+use napi::{Env, Error, JsFunction, JsObject, JsUnknown, Result, ValueType, sys};
+use std::collections::VecDeque;
+use std::ptr;
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+/// Default capacity of the watch queue.
+const DEFAULT_WATCH_CAPACITY: u32 = 16;
+
+/// What to do when a change comes but the watch queue is full.
+#[napi(string_enum = "kebab-case")]
+pub enum OverflowPolicy {
+  /// Drop the oldest change in queue, keep the newer ones.
+  DropOldest,
+  /// Keep the latest change only, every change replaces the one in queue whether it is full or not.
+  CoalesceLatest,
+}
+
+#[napi(object)]
+pub struct WatchOptions {
+  /// Max changes buffered before consumed, default 16
+  pub capacity: Option<u32>,
+  /// What to do when the buffer is full, default 'drop-oldest'. 'coalesce-latest' buffers the newest change only
+  pub overflow: Option<OverflowPolicy>,
+}
+
+/// A bounded queue between the listener of nacos-sdk and the async iterator of js.
+pub(crate) struct WatchQueue<T> {
+  state: Mutex<WatchState<T>>,
+  notify: Notify,
+  capacity: usize,
+  overflow: OverflowPolicy,
+}
+
+struct WatchState<T> {
+  items: VecDeque<T>,
+  closed: bool,
+  error: Option<String>,
+}
+
+impl<T> WatchQueue<T> {
+  pub(crate) fn new(options: Option<WatchOptions>) -> Self {
+    let (capacity, overflow) = match options {
+      Some(options) => (options.capacity, options.overflow),
+      None => (None, None),
+    };
+    WatchQueue {
+      state: Mutex::new(WatchState {
+        items: VecDeque::new(),
+        closed: false,
+        error: None,
+      }),
+      notify: Notify::new(),
+      capacity: capacity.unwrap_or(DEFAULT_WATCH_CAPACITY).max(1) as usize,
+      overflow: overflow.unwrap_or(OverflowPolicy::DropOldest),
+    }
+  }
+
+  /// Push a change, never block the listener. Ignored after closed.
+  pub(crate) fn push(&self, item: T) {
+    {
+      let mut state = self.state.lock().unwrap();
+      if state.closed {
+        return;
+      }
+      match self.overflow {
+        OverflowPolicy::DropOldest => {
+          if state.items.len() >= self.capacity {
+            state.items.pop_front();
+          }
+        }
+        // the consumer only cares about the newest one, even if it is slow
+        OverflowPolicy::CoalesceLatest => state.items.clear(),
+      }
+      state.items.push_back(item);
+    }
+    self.notify.notify_waiters();
+  }
+
+  /// Wait for the next change, None after closed, Err if it was closed by [`WatchQueue::fail`].
+  pub(crate) async fn recv(&self) -> Result<Option<T>> {
+    loop {
+      let mut notified = std::pin::pin!(self.notify.notified());
+      notified.as_mut().enable();
+      {
+        let mut state = self.state.lock().unwrap();
+        if let Some(reason) = state.error.take() {
+          return Err(Error::from_reason(reason));
+        }
+        if state.closed {
+          return Ok(None);
+        }
+        if let Some(item) = state.items.pop_front() {
+          return Ok(Some(item));
+        }
+      }
+      notified.await;
+    }
+  }
+
+  /// Close the queue, drop the buffered changes and wake up all waiting `recv`.
+  pub(crate) fn close(&self) {
+    {
+      let mut state = self.state.lock().unwrap();
+      state.closed = true;
+      state.items.clear();
+    }
+    self.notify.notify_waiters();
+  }
+
+  /// Close the queue by an error, the next `recv` get it.
+  pub(crate) fn fail(&self, reason: String) {
+    {
+      let mut state = self.state.lock().unwrap();
+      state.error = Some(reason);
+    }
+    self.close();
+  }
+}
+
+/// Set `[Symbol.asyncIterator]` which return itself, so that the watcher works with `for await`.
+pub(crate) fn set_async_iterator(env: &Env, object: &mut JsObject) -> Result<()> {
+  let symbol: JsFunction = env.get_global()?.get_named_property("Symbol")?;
+  let async_iterator: JsUnknown = symbol
+    .coerce_to_object()?
+    .get_named_property("asyncIterator")?;
+  if async_iterator.get_type()? == ValueType::Symbol {
+    let iterator = env.create_function("asyncIterator", return_this)?;
+    object.set_property(async_iterator, iterator)?;
+  }
+  Ok(())
+}
+
+/// Native callback of `function () { return this }`.
+unsafe extern "C" fn return_this(
+  env: sys::napi_env,
+  info: sys::napi_callback_info,
+) -> sys::napi_value {
+  let mut this = ptr::null_mut();
+  unsafe {
+    sys::napi_get_cb_info(
+      env,
+      info,
+      ptr::null_mut(),
+      ptr::null_mut(),
+      &mut this,
+      ptr::null_mut(),
+    );
+  }
+  this
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{OverflowPolicy, WatchOptions, WatchQueue};
+  use std::time::Duration;
+
+  /// Push the changes before any is consumed, then receive the buffered ones.
+  fn received(overflow: OverflowPolicy, changes: u32) -> Vec<u32> {
+    let queue = WatchQueue::new(Some(WatchOptions {
+      capacity: None,
+      overflow: Some(overflow),
+    }));
+    (1..=changes).for_each(|change| queue.push(change));
+    crate::get_runtime().block_on(async {
+      let mut received = Vec::new();
+      while let Ok(change) = tokio::time::timeout(Duration::from_millis(10), queue.recv()).await {
+        received.extend(change.unwrap());
+      }
+      received
+    })
+  }
+
+  #[test]
+  fn coalesce_latest_keeps_newest_change_only() {
+    assert_eq!(received(OverflowPolicy::CoalesceLatest, 5), [5]);
+  }
+
+  #[test]
+  fn drop_oldest_keeps_changes_in_capacity() {
+    assert_eq!(received(OverflowPolicy::DropOldest, 5), [1, 2, 3, 4, 5]);
+    assert_eq!(
+      received(OverflowPolicy::DropOldest, 20),
+      (5..=20).collect::<Vec<_>>()
+    );
+  }
+}